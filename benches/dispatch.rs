@@ -0,0 +1,74 @@
+//! Time the driver takes to dispatch readiness while threads poll the same resources, a
+//! dispatch that contends with the pollers slows down as they are added.
+
+use std::net::UdpSocket;
+use std::os::unix::io::AsRawFd;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use executor::io::{fd_source, Driver, Interest, Ready, Registration};
+
+const SOCKETS: usize = 256;
+const EVENTS: usize = 1 << 20;
+
+fn main() {
+    for &pollers in &[0, 1, 2, 4, 8] {
+        let elapsed = dispatch(pollers);
+        println!(
+            "{} pollers: {:?} per event",
+            pollers,
+            elapsed / EVENTS as u32
+        );
+    }
+}
+
+/// Dispatches `EVENTS` readable events over `SOCKETS` resources while `pollers` threads keep
+/// taking their readiness.
+fn dispatch(pollers: usize) -> Duration {
+    let driver = Driver::new();
+    let sockets: Vec<_> = (0..SOCKETS)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect();
+    let registrations: Arc<Vec<_>> = Arc::new(
+        sockets
+            .iter()
+            .map(|socket| {
+                let fd = socket.as_raw_fd();
+                let mut source = fd_source(&fd);
+                Registration::new(driver.handle(), &mut source, Interest::readable()).unwrap()
+            })
+            .collect(),
+    );
+    let tokens: Vec<_> = registrations.iter().map(Registration::token).collect();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let threads: Vec<_> = (0..pollers)
+        .map(|_| {
+            let registrations = registrations.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    for registration in registrations.iter() {
+                        registration.take_read_ready().unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let start = Instant::now();
+    for i in 0..EVENTS {
+        driver.dispatch(tokens[i % SOCKETS], Ready::readable());
+    }
+    let elapsed = start.elapsed();
+
+    stop.store(true, Ordering::Relaxed);
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    elapsed
+}
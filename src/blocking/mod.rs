@@ -0,0 +1,3 @@
+mod pool;
+
+pub use pool::{BlockingPool, DEFAULT_KEEP_ALIVE, DEFAULT_MAX_THREADS, DEFAULT_SHUTDOWN_TIMEOUT};
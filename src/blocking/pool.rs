@@ -0,0 +1,202 @@
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    ptr,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::scheduler::{self, JoinHandle};
+use crate::util::ThreadConfig;
+
+/// Upper bound on the number of threads used by the pool of an executor.
+pub const DEFAULT_MAX_THREADS: usize = 512;
+/// How long a thread of the pool waits for work before exiting.
+pub const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(10);
+/// How long dropping an executor waits for the closures of its pool to finish.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+thread_local! {
+    /// Pool of the current thread, if it is one of its threads.
+    static CURRENT: Cell<*const Inner> = const { Cell::new(ptr::null()) };
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Pool of threads running closures that would block an executor.
+///
+/// Threads are started on demand, up to `max_threads`, and exit after staying idle for
+/// `keep_alive`. When every thread is busy the closures are queued.
+pub struct BlockingPool {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    state: Mutex<State>,
+    /// Signaled when a closure is queued or the pool shuts down.
+    condvar: Condvar,
+    /// Signaled when the last thread exits.
+    exited: Condvar,
+    max_threads: usize,
+    keep_alive: Duration,
+    threads: ThreadConfig,
+}
+
+struct State {
+    queue: VecDeque<Task>,
+    num_threads: usize,
+    num_idle: usize,
+    /// Threads that left the pool but are still running the stop hook.
+    num_stopping: usize,
+    shutdown: bool,
+}
+
+impl BlockingPool {
+    pub fn new(name: impl Into<String>, max_threads: usize, keep_alive: Duration) -> Self {
+        Self::with_threads(ThreadConfig::new(name), max_threads, keep_alive)
+    }
+
+    /// Threads of the pool are named after `threads` and run its hooks.
+    pub fn with_threads(threads: ThreadConfig, max_threads: usize, keep_alive: Duration) -> Self {
+        assert!(
+            max_threads > 0,
+            "BlockingPool: max_threads must be at least 1"
+        );
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    queue: VecDeque::new(),
+                    num_threads: 0,
+                    num_idle: 0,
+                    num_stopping: 0,
+                    shutdown: false,
+                }),
+                condvar: Condvar::new(),
+                exited: Condvar::new(),
+                max_threads,
+                keep_alive,
+                threads,
+            }),
+        }
+    }
+
+    /// Runs `f` on a thread of the pool. The handle resolves to `JoinError::Cancelled` if the
+    /// pool was shut down.
+    pub fn spawn<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (task, handle) = scheduler::blocking(f);
+
+        let mut state = self.inner.state.lock().unwrap();
+        if state.shutdown {
+            return handle;
+        }
+        state.queue.push_back(Box::new(task));
+
+        // Idle threads are only decremented once they wake up, compare them with the queued
+        // closures rather than checking for any idle thread.
+        if state.queue.len() > state.num_idle && state.num_threads < self.inner.max_threads {
+            let inner = self.inner.clone();
+            let name = self.inner.threads.name().to_string();
+            let spawned = self.inner.threads.builder(name).spawn(move || inner.run());
+            match spawned {
+                Ok(_) => state.num_threads += 1,
+                // Nobody would ever run the closure, cancel it.
+                Err(_) if state.num_threads == 0 => drop(state.queue.pop_back()),
+                // The closure waits for a busy thread.
+                Err(_) => {}
+            }
+        }
+        self.inner.condvar.notify_one();
+
+        handle
+    }
+
+    /// Stops accepting closures and waits up to `timeout` (forever if `None`) for the threads
+    /// to run what was queued and exit. Returns false if some are still running.
+    ///
+    /// Returns false right away when called from a thread of the pool, it would wait for
+    /// itself.
+    pub fn shutdown(&self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        let mut state = self.inner.state.lock().unwrap();
+        state.shutdown = true;
+        self.inner.condvar.notify_all();
+
+        if CURRENT.with(|current| ptr::eq(current.get(), &*self.inner)) {
+            return false;
+        }
+
+        while state.num_threads > 0 || state.num_stopping > 0 {
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    self.inner
+                        .exited
+                        .wait_timeout(state, deadline - now)
+                        .unwrap()
+                        .0
+                }
+                None => self.inner.exited.wait(state).unwrap(),
+            };
+        }
+        true
+    }
+}
+
+impl Drop for BlockingPool {
+    /// Threads are not waited for, they exit once the queued closures ran.
+    fn drop(&mut self) {
+        self.inner.state.lock().unwrap().shutdown = true;
+        self.inner.condvar.notify_all();
+    }
+}
+
+impl Inner {
+    fn run(&self) {
+        CURRENT.with(|current| current.set(self));
+        self.threads.started();
+
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(task) = state.queue.pop_front() {
+                drop(state);
+                task();
+                state = self.state.lock().unwrap();
+                continue;
+            }
+            if state.shutdown {
+                break;
+            }
+
+            state.num_idle += 1;
+            let (guard, wait) = self.condvar.wait_timeout(state, self.keep_alive).unwrap();
+            state = guard;
+            state.num_idle -= 1;
+
+            if wait.timed_out() && state.queue.is_empty() && !state.shutdown {
+                break;
+            }
+        }
+
+        // The closures queued from now on are left to the other threads or a new one.
+        state.num_threads -= 1;
+        state.num_stopping += 1;
+        drop(state);
+
+        self.threads.stopped();
+        CURRENT.with(|current| current.set(ptr::null()));
+
+        let mut state = self.state.lock().unwrap();
+        state.num_stopping -= 1;
+        if state.num_threads == 0 && state.num_stopping == 0 {
+            self.exited.notify_all();
+        }
+    }
+}
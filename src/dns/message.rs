@@ -0,0 +1,162 @@
+//! Just enough of the DNS wire format (RFC 1035) to ask for the A and AAAA records of a name.
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+/// Largest message accepted over UDP without EDNS.
+pub const MAX_UDP_SIZE: usize = 512;
+/// Length of the fixed header preceding the question section.
+const HEADER_SIZE: usize = 12;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+const FLAG_RESPONSE: u16 = 1 << 15;
+const FLAG_TRUNCATED: u16 = 1 << 9;
+const FLAG_RECURSION_DESIRED: u16 = 1 << 8;
+const RCODE_MASK: u16 = 0xf;
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// Outcome of a response matching one of our queries.
+#[derive(Debug)]
+pub enum Answer {
+    Addrs(Vec<IpAddr>),
+    NameError,
+    /// Any other error code returned by the nameserver.
+    ServerError(u16),
+    /// The response did not fit in a datagram, the query must be sent again over TCP.
+    Truncated,
+}
+
+/// Encodes a recursive query for the `qtype` records of `name`.
+pub fn query(id: u16, name: &str, qtype: u16) -> io::Result<Vec<u8>> {
+    let name = name.trim_end_matches('.');
+    if name.is_empty() || name.len() > 253 {
+        return Err(invalid_input("invalid host name"));
+    }
+
+    let mut buf = Vec::with_capacity(18 + name.len());
+    buf.extend_from_slice(&id.to_be_bytes());
+    buf.extend_from_slice(&FLAG_RECURSION_DESIRED.to_be_bytes());
+    // One question, no answer, authority or additional record.
+    buf.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(invalid_input("invalid host name"));
+        }
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0);
+    buf.extend_from_slice(&qtype.to_be_bytes());
+    buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(buf)
+}
+
+/// Id of a response, `None` if `buf` is too short to be one.
+pub fn response_id(buf: &[u8]) -> Option<u16> {
+    if buf.len() < HEADER_SIZE {
+        return None;
+    }
+    Some(u16::from_be_bytes([buf[0], buf[1]]))
+}
+
+/// Parses the response to `query`, every A and AAAA record of the answer section is kept
+/// whatever its owner, which follows the CNAME chains resolved by the nameserver.
+///
+/// Fails if the response does not repeat the question of `query`.
+pub fn parse(buf: &[u8], query: &[u8]) -> io::Result<Answer> {
+    let mut r = Reader { buf, pos: 0 };
+    let _id = r.u16()?;
+    let flags = r.u16()?;
+    let qdcount = r.u16()?;
+    let ancount = r.u16()?;
+    r.skip(4)?;
+
+    if flags & FLAG_RESPONSE == 0 {
+        return Err(invalid_data("not a response"));
+    }
+    // Our queries have a single question, names are compared without case (RFC 4343).
+    let question = &query[HEADER_SIZE..];
+    if qdcount != 1 || !r.take(question.len())?.eq_ignore_ascii_case(question) {
+        return Err(invalid_data("response to another question"));
+    }
+    if flags & FLAG_TRUNCATED != 0 {
+        return Ok(Answer::Truncated);
+    }
+    match flags & RCODE_MASK {
+        0 => {}
+        RCODE_NXDOMAIN => return Ok(Answer::NameError),
+        rcode => return Ok(Answer::ServerError(rcode)),
+    }
+
+    let mut addrs = Vec::new();
+    for _ in 0..ancount {
+        r.skip_name()?;
+        let rtype = r.u16()?;
+        let class = r.u16()?;
+        r.skip(4)?;
+        let len = r.u16()? as usize;
+        let data = r.take(len)?;
+        match (rtype, class, len) {
+            (TYPE_A, CLASS_IN, 4) => addrs.push(IpAddr::V4(Ipv4Addr::new(
+                data[0], data[1], data[2], data[3],
+            ))),
+            (TYPE_AAAA, CLASS_IN, 16) => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(data);
+                addrs.push(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            _ => {}
+        }
+    }
+    Ok(Answer::Addrs(addrs))
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let end = self.pos + n;
+        if end > self.buf.len() {
+            return Err(invalid_data("truncated message"));
+        }
+        let data = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(data)
+    }
+    fn skip(&mut self, n: usize) -> io::Result<()> {
+        self.take(n).map(|_| ())
+    }
+    fn u16(&mut self) -> io::Result<u16> {
+        let data = self.take(2)?;
+        Ok(u16::from_be_bytes([data[0], data[1]]))
+    }
+
+    /// Skips a possibly compressed name, the names themselves are never needed.
+    fn skip_name(&mut self) -> io::Result<()> {
+        loop {
+            let len = self.take(1)?[0];
+            match len {
+                0 => return Ok(()),
+                len if len & 0xc0 == 0xc0 => return self.skip(1),
+                len if len & 0xc0 == 0 => self.skip(len as usize)?,
+                _ => return Err(invalid_data("invalid label")),
+            }
+        }
+    }
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,85 @@
+mod message;
+mod native;
+
+use std::{
+    io,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+};
+
+use crate::blocking::{BlockingPool, DEFAULT_KEEP_ALIVE};
+use crate::io::Handle;
+use crate::time;
+
+pub use native::Config;
+
+/// Threads of the pool running `getaddrinfo` for `Resolver::system`.
+const SYSTEM_THREADS: usize = 4;
+
+/// Resolves host names without blocking the executor.
+pub struct Resolver {
+    mode: Mode,
+}
+
+enum Mode {
+    System(BlockingPool),
+    Native {
+        handle: Handle,
+        timer: time::Handle,
+        config: Config,
+    },
+}
+
+impl Resolver {
+    /// Calls the system resolver (`getaddrinfo`) on a dedicated blocking pool.
+    pub fn system() -> Self {
+        Self {
+            mode: Mode::System(BlockingPool::new(
+                "dns-resolver",
+                SYSTEM_THREADS,
+                DEFAULT_KEEP_ALIVE,
+            )),
+        }
+    }
+
+    /// Looks the name up in the hosts file, then asks the nameserver of `config` over UDP
+    /// through the IO driver.
+    pub fn native(handle: Handle, timer: time::Handle, config: Config) -> Self {
+        Self {
+            mode: Mode::Native {
+                handle,
+                timer,
+                config,
+            },
+        }
+    }
+
+    /// Resolves `host` into addresses with the given port, IP literals are returned as is.
+    pub async fn lookup_host(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![SocketAddr::new(ip, port)]);
+        }
+
+        match &self.mode {
+            Mode::System(pool) => {
+                let host = host.to_owned();
+                pool.spawn(move || {
+                    (host.as_str(), port)
+                        .to_socket_addrs()
+                        .map(Iterator::collect)
+                })
+                .await?
+            }
+            Mode::Native {
+                handle,
+                timer,
+                config,
+            } => {
+                let ips = native::lookup(handle, timer, config, host).await?;
+                Ok(ips
+                    .into_iter()
+                    .map(|ip| SocketAddr::new(ip, port))
+                    .collect())
+            }
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    collections::hash_map::RandomState,
+    fs,
+    hash::{BuildHasher, Hasher},
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::message::{self, Answer};
+use crate::io::Handle;
+use crate::tcp::TcpStream;
+use crate::time;
+use crate::udp::UdpSocket;
+
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Settings of the resolver used by `Resolver::native`.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub nameserver: SocketAddr,
+    /// Read before querying the nameserver.
+    pub hosts: PathBuf,
+    /// How long to wait for the answers before sending the queries again.
+    pub timeout: Duration,
+    /// How many times the queries are sent.
+    pub attempts: usize,
+}
+
+impl Config {
+    pub fn new(nameserver: SocketAddr) -> Self {
+        Self {
+            nameserver,
+            hosts: PathBuf::from("/etc/hosts"),
+            timeout: Duration::from_secs(5),
+            attempts: 2,
+        }
+    }
+
+    /// Uses the first nameserver of `/etc/resolv.conf`.
+    pub fn from_system() -> io::Result<Self> {
+        let conf = fs::read_to_string("/etc/resolv.conf")?;
+        conf.lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                match words.next() {
+                    Some("nameserver") => words.next()?.parse::<IpAddr>().ok(),
+                    _ => None,
+                }
+            })
+            .next()
+            .map(|ip| Self::new(SocketAddr::new(ip, 53)))
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "no nameserver in /etc/resolv.conf")
+            })
+    }
+}
+
+pub(super) async fn lookup(
+    handle: &Handle,
+    timer: &time::Handle,
+    config: &Config,
+    host: &str,
+) -> io::Result<Vec<IpAddr>> {
+    // A missing or unreadable hosts file is not an error, the nameserver is asked instead.
+    let addrs = hosts_lookup(&config.hosts, host).unwrap_or_default();
+    if !addrs.is_empty() {
+        return Ok(addrs);
+    }
+
+    let local: SocketAddr = match config.nameserver {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(handle.clone(), local)?;
+    socket.connect(config.nameserver)?;
+
+    let mut queries = Vec::new();
+    for &qtype in &[message::TYPE_AAAA, message::TYPE_A] {
+        let id = random_id();
+        queries.push((id, message::query(id, host, qtype)?, None));
+    }
+
+    for _ in 0..config.attempts {
+        for (_, query, answer) in &queries {
+            if answer.is_none() {
+                socket.send(query).await?;
+            }
+        }
+        let receive = receive(&socket, &mut queries);
+        match time::timeout(timer, config.timeout, receive).await {
+            Ok(res) => res?,
+            Err(_) => continue,
+        }
+        break;
+    }
+
+    // Answers that did not fit in a datagram are asked again over TCP.
+    let mut tcp_error = None;
+    for (_, query, answer) in &mut queries {
+        if let Some(Answer::Truncated) = answer {
+            let tcp = query_tcp(handle, config.nameserver, query);
+            match time::timeout(timer, config.timeout, tcp).await {
+                Ok(Ok(found)) => *answer = Some(found),
+                Ok(Err(e)) => tcp_error = Some(e),
+                Err(e) => tcp_error = Some(e.into()),
+            }
+        }
+    }
+
+    let mut addrs = Vec::new();
+    let mut name_error = false;
+    let mut server_error = None;
+    for (_, _, answer) in queries {
+        match answer {
+            Some(Answer::Addrs(found)) => addrs.extend(found),
+            Some(Answer::NameError) => name_error = true,
+            Some(Answer::ServerError(rcode)) => server_error = Some(rcode),
+            Some(Answer::Truncated) | None => {}
+        }
+    }
+    if !addrs.is_empty() {
+        Ok(addrs)
+    } else if name_error {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "name does not exist",
+        ))
+    } else if let Some(e) = tcp_error {
+        Err(e)
+    } else if let Some(rcode) = server_error {
+        Err(io::Error::other(format!(
+            "nameserver returned error code {}",
+            rcode
+        )))
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "nameserver did not answer",
+        ))
+    }
+}
+
+/// Receives until every query has an answer, responses with an unknown id, that do not parse
+/// or that answer another question are dropped since anyone can send them.
+async fn receive(
+    socket: &UdpSocket,
+    queries: &mut [(u16, Vec<u8>, Option<Answer>)],
+) -> io::Result<()> {
+    let mut buf = [0; message::MAX_UDP_SIZE];
+    while queries.iter().any(|(_, _, answer)| answer.is_none()) {
+        let n = socket.recv(&mut buf).await?;
+        let id = match message::response_id(&buf[..n]) {
+            Some(id) => id,
+            None => continue,
+        };
+        let pending = queries
+            .iter_mut()
+            .find(|(qid, _, answer)| *qid == id && answer.is_none());
+        if let Some((_, query, answer)) = pending {
+            *answer = message::parse(&buf[..n], query).ok();
+        }
+    }
+    Ok(())
+}
+
+/// Sends `query` over TCP, where messages are prefixed by their length (RFC 1035 4.2.2).
+async fn query_tcp(handle: &Handle, nameserver: SocketAddr, query: &[u8]) -> io::Result<Answer> {
+    let mut stream = TcpStream::connect(handle.clone(), nameserver).await?;
+
+    let mut msg = Vec::with_capacity(2 + query.len());
+    msg.extend_from_slice(&(query.len() as u16).to_be_bytes());
+    msg.extend_from_slice(query);
+    stream.write_all(&msg).await?;
+
+    let mut len = [0; 2];
+    stream.read_exact(&mut len).await?;
+    let mut buf = vec![0; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut buf).await?;
+
+    if message::response_id(&buf) != message::response_id(query) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "response to another query",
+        ));
+    }
+    match message::parse(&buf, query)? {
+        Answer::Truncated => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "truncated response over TCP",
+        )),
+        answer => Ok(answer),
+    }
+}
+
+fn hosts_lookup(path: &Path, host: &str) -> io::Result<Vec<IpAddr>> {
+    let host = host.trim_end_matches('.');
+    let content = fs::read_to_string(path)?;
+
+    let mut addrs = Vec::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let ip = match words.next().map(str::parse::<IpAddr>) {
+            Some(Ok(ip)) => ip,
+            _ => continue,
+        };
+        if words.any(|name| name.eq_ignore_ascii_case(host)) && !addrs.contains(&ip) {
+            addrs.push(ip);
+        }
+    }
+    Ok(addrs)
+}
+
+/// Query ids are picked at random to make spoofed responses harder to forge.
+fn random_id() -> u16 {
+    RandomState::new().build_hasher().finish() as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{Read, Write},
+        net::{self, IpAddr, SocketAddr},
+        path::PathBuf,
+        thread,
+        time::Duration,
+    };
+
+    use super::{hosts_lookup, lookup, message, Config};
+    use crate::runtime::Builder;
+
+    const LOCALHOST: &str = "127.0.0.1:0";
+
+    /// Response to `query` carrying the addresses of `addrs` matching the queried type.
+    fn respond(query: &[u8], rcode: u16, truncated: bool, addrs: &[IpAddr]) -> Vec<u8> {
+        let qtype = u16::from_be_bytes([query[query.len() - 4], query[query.len() - 3]]);
+        let records: Vec<_> = addrs
+            .iter()
+            .filter_map(|ip| match (ip, qtype) {
+                (IpAddr::V4(ip), message::TYPE_A) => Some((message::TYPE_A, ip.octets().to_vec())),
+                (IpAddr::V6(ip), message::TYPE_AAAA) => {
+                    Some((message::TYPE_AAAA, ip.octets().to_vec()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let flags = 0x8180 | rcode | if truncated { 1 << 9 } else { 0 };
+        let mut buf = query[..2].to_vec();
+        buf.extend_from_slice(&flags.to_be_bytes());
+        buf.extend_from_slice(&[0, 1]);
+        buf.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+        buf.extend_from_slice(&query[12..]);
+        for (rtype, data) in records {
+            // Owner name pointing at the question, class IN and a TTL of a minute.
+            buf.extend_from_slice(&[0xc0, 12]);
+            buf.extend_from_slice(&rtype.to_be_bytes());
+            buf.extend_from_slice(&[0, 1, 0, 0, 0, 60]);
+            buf.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            buf.extend_from_slice(&data);
+        }
+        buf
+    }
+
+    fn config(nameserver: SocketAddr) -> Config {
+        let mut config = Config::new(nameserver);
+        config.hosts = PathBuf::from("/nonexistent/hosts");
+        config.timeout = Duration::from_millis(500);
+        config
+    }
+
+    fn resolve(config: &Config, host: &str) -> std::io::Result<Vec<IpAddr>> {
+        let mut rt = Builder::new_current_thread().enable_all().build().unwrap();
+        let handle = rt.handle().io();
+        let timer = rt.handle().timer();
+        rt.block_on(async move { lookup(&handle, &timer, config, host).await })
+    }
+
+    #[test]
+    fn skips_bogus_datagrams() {
+        let server = net::UdpSocket::bind(LOCALHOST).unwrap();
+        let nameserver = server.local_addr().unwrap();
+
+        let stub = thread::spawn(move || {
+            let mut buf = [0; 512];
+            for _ in 0..2 {
+                let (n, peer) = server.recv_from(&mut buf).unwrap();
+                let query = &buf[..n];
+                let mut other = query.to_vec();
+                // Same id, other name: "example.com" becomes "exbmple.com".
+                other[15] = b'b';
+
+                server.send_to(b"garbage", peer).unwrap();
+                server.send_to(&query[..14], peer).unwrap();
+                let addrs = ["10.0.0.1".parse().unwrap()];
+                server
+                    .send_to(&respond(&other, 0, false, &addrs), peer)
+                    .unwrap();
+                let addrs = ["127.0.0.2".parse().unwrap(), "::2".parse().unwrap()];
+                server
+                    .send_to(&respond(query, 0, false, &addrs), peer)
+                    .unwrap();
+            }
+        });
+
+        let mut addrs = resolve(&config(nameserver), "example.com").unwrap();
+        addrs.sort();
+        let expected: Vec<IpAddr> = vec!["127.0.0.2".parse().unwrap(), "::2".parse().unwrap()];
+        assert_eq!(addrs, expected);
+        stub.join().unwrap();
+    }
+
+    #[test]
+    fn reports_server_errors() {
+        let server = net::UdpSocket::bind(LOCALHOST).unwrap();
+        let nameserver = server.local_addr().unwrap();
+
+        let stub = thread::spawn(move || {
+            let mut buf = [0; 512];
+            for _ in 0..2 {
+                let (n, peer) = server.recv_from(&mut buf).unwrap();
+                // SERVFAIL
+                server
+                    .send_to(&respond(&buf[..n], 2, false, &[]), peer)
+                    .unwrap();
+            }
+        });
+
+        let e = resolve(&config(nameserver), "example.com").unwrap_err();
+        assert!(e.to_string().contains("error code 2"), "{}", e);
+        stub.join().unwrap();
+    }
+
+    #[test]
+    fn retries_truncated_answers_over_tcp() {
+        let server = net::UdpSocket::bind(LOCALHOST).unwrap();
+        let nameserver = server.local_addr().unwrap();
+        let listener = net::TcpListener::bind(nameserver).unwrap();
+        let addrs: Vec<IpAddr> = vec!["127.0.0.3".parse().unwrap()];
+
+        let udp = thread::spawn(move || {
+            let mut buf = [0; 512];
+            for _ in 0..2 {
+                let (n, peer) = server.recv_from(&mut buf).unwrap();
+                server
+                    .send_to(&respond(&buf[..n], 0, true, &[]), peer)
+                    .unwrap();
+            }
+        });
+        let answers = addrs.clone();
+        let tcp = thread::spawn(move || {
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut len = [0; 2];
+                stream.read_exact(&mut len).unwrap();
+                let mut query = vec![0; u16::from_be_bytes(len) as usize];
+                stream.read_exact(&mut query).unwrap();
+
+                let response = respond(&query, 0, false, &answers);
+                stream
+                    .write_all(&(response.len() as u16).to_be_bytes())
+                    .unwrap();
+                stream.write_all(&response).unwrap();
+            }
+        });
+
+        assert_eq!(resolve(&config(nameserver), "example.com").unwrap(), addrs);
+        udp.join().unwrap();
+        tcp.join().unwrap();
+    }
+
+    #[test]
+    fn hosts_file() {
+        let path = std::env::temp_dir().join(format!("executor-hosts-{}", std::process::id()));
+        fs::write(
+            &path,
+            "# comment\n\
+             127.0.0.1\tlocalhost\n\
+             ::1 localhost ip6-localhost # trailing comment\n\
+             10.0.0.1 Server.Example server\n\
+             10.0.0.1 server\n\
+             not-an-ip server\n\
+             # 10.0.0.2 server\n",
+        )
+        .unwrap();
+
+        let lookup = |host| hosts_lookup(&path, host).unwrap();
+        let localhost: Vec<IpAddr> = vec!["127.0.0.1".parse().unwrap(), "::1".parse().unwrap()];
+        assert_eq!(lookup("localhost"), localhost);
+        let server: Vec<IpAddr> = vec!["10.0.0.1".parse().unwrap()];
+        assert_eq!(lookup("server"), server);
+        assert_eq!(lookup("server.example."), server);
+        assert_eq!(lookup("example"), Vec::<IpAddr>::new());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -35,7 +35,6 @@ pin_project! {
     }
 }
 impl<T, U> Framed<T, U> {
-    #[allow(dead_code)]
     pub fn new(io: T, codec: U) -> Self {
         Self {
             inner: stream(
@@ -47,7 +46,6 @@ impl<T, U> Framed<T, U> {
             ),
         }
     }
-    #[allow(dead_code)]
     pub fn from_parts(parts: FramedParts<T, U>) -> Self {
         let FramedParts {
             io,
@@ -52,12 +52,12 @@ where
     }
 
     fn start_send(self: Pin<&mut Self>, item: I) -> Result<(), Self::Error> {
-        let mut pinned = self.project();
+        let pinned = self.project();
         pinned
             .inner
             .project()
             .codec
-            .encode(item, &mut pinned.buff)?;
+            .encode(item, pinned.buff)?;
         Ok(())
     }
 
@@ -66,7 +66,7 @@ where
 
         while !pinned.buff.is_empty() {
             let buf = &pinned.buff;
-            let n = ready!(pinned.inner.as_mut().poll_write(cx, &buf))?;
+            let n = ready!(pinned.inner.as_mut().poll_write(cx, buf))?;
 
             if n == 0 {
                 return Poll::Ready(Err(io::Error::new(
@@ -47,7 +47,7 @@ where
         loop {
             if *pinned.is_readable {
                 if *pinned.is_eof {
-                    let frame = pinned.inner.project().codec.decode_eof(&mut pinned.buff)?;
+                    let frame = pinned.inner.project().codec.decode_eof(pinned.buff)?;
                     return Poll::Ready(frame.map(Ok));
                 }
 
@@ -56,7 +56,7 @@ where
                     .as_mut()
                     .project()
                     .codec
-                    .decode(&mut pinned.buff)?
+                    .decode(pinned.buff)?
                 {
                     return Poll::Ready(Some(Ok(frame)));
                 }
@@ -70,7 +70,7 @@ where
             // got room for at least one byte to read to ensure that we don't
             // get a spurious 0 that looks like EOF
             pinned.buff.reserve(1);
-            let bytect = match pinned.inner.as_mut().poll_read(cx, &mut pinned.buff)? {
+            let bytect = match pinned.inner.as_mut().poll_read(cx, pinned.buff)? {
                 Poll::Ready(ct) => ct,
                 Poll::Pending => return Poll::Pending,
             };
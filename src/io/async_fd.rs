@@ -0,0 +1,179 @@
+use std::{
+    fmt, io,
+    os::unix::io::{AsRawFd, RawFd},
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
+
+use super::{sys, Direction, Handle, Interest, Ready, Registration};
+
+use futures::ready;
+
+/// Registers any file descriptor with the driver (eventfd, timerfd, inotify, netlink, ...).
+///
+/// `AsyncFd` does not perform IO itself, it only reports readiness: the caller does the IO on
+/// the wrapped object and clears the readiness through the guard when it hits `WouldBlock`.
+/// The file descriptor must be in non-blocking mode.
+pub struct AsyncFd<T: AsRawFd> {
+    registration: Registration,
+    inner: Option<T>,
+    read_readiness: AtomicUsize,
+    write_readiness: AtomicUsize,
+}
+
+/// Readiness observed by `AsyncFd::readable` or `AsyncFd::writable`, it is kept until cleared.
+pub struct AsyncFdReadyGuard<'a, T: AsRawFd> {
+    fd: &'a AsyncFd<T>,
+    direction: Direction,
+    ready: Ready,
+}
+
+/// The IO attempted through `AsyncFdReadyGuard::try_io` would have blocked.
+#[derive(Debug)]
+pub struct TryIoError(());
+
+impl<T: AsRawFd> AsyncFd<T> {
+    pub fn new(handle: Handle, inner: T) -> io::Result<Self> {
+        Self::with_interest(handle, inner, Interest::both())
+    }
+
+    pub fn with_interest(handle: Handle, inner: T, interest: Interest) -> io::Result<Self> {
+        let fd = inner.as_raw_fd();
+        let registration = Registration::new(handle, &mut sys::fd_source(&fd), interest)?;
+        Ok(Self {
+            registration,
+            inner: Some(inner),
+            read_readiness: AtomicUsize::new(0),
+            write_readiness: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn get_ref(&self) -> &T {
+        self.inner.as_ref().unwrap()
+    }
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Deregisters the file descriptor and gives back the wrapped object.
+    pub fn into_inner(mut self) -> T {
+        let _ = self.deregister();
+        self.inner.take().unwrap()
+    }
+
+    pub fn poll_read_ready(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        self.poll_ready(cx, Direction::Read)
+    }
+    pub fn poll_write_ready(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        self.poll_ready(cx, Direction::Write)
+    }
+
+    pub async fn readable(&self) -> io::Result<AsyncFdReadyGuard<'_, T>> {
+        futures::future::poll_fn(|cx| self.poll_read_ready(cx)).await
+    }
+    pub async fn writable(&self) -> io::Result<AsyncFdReadyGuard<'_, T>> {
+        futures::future::poll_fn(|cx| self.poll_write_ready(cx)).await
+    }
+
+    fn poll_ready(
+        &self,
+        cx: &mut Context<'_>,
+        direction: Direction,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        let cache = self.cache(direction);
+        let mut ready = Ready::from_usize(cache.load(Ordering::Acquire)) & direction.mask();
+
+        if ready.is_empty() {
+            // Nothing cached, consume the readiness reported by the driver.
+            ready = match direction {
+                Direction::Read => ready!(self.registration.poll_read_ready(cx))?,
+                Direction::Write => ready!(self.registration.poll_write_ready(cx))?,
+            };
+            cache.fetch_or(ready.as_usize(), Ordering::AcqRel);
+        }
+
+        Poll::Ready(Ok(AsyncFdReadyGuard {
+            fd: self,
+            direction,
+            ready,
+        }))
+    }
+
+    fn cache(&self, direction: Direction) -> &AtomicUsize {
+        match direction {
+            Direction::Read => &self.read_readiness,
+            Direction::Write => &self.write_readiness,
+        }
+    }
+
+    fn deregister(&mut self) -> io::Result<()> {
+        let fd = self.get_ref().as_raw_fd();
+        let mut source = sys::fd_source(&fd);
+        self.registration.deregister(&mut source)
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for AsyncFd<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.get_ref().as_raw_fd()
+    }
+}
+
+impl<T: AsRawFd> Drop for AsyncFd<T> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.deregister();
+        }
+    }
+}
+
+impl<'a, T: AsRawFd> AsyncFdReadyGuard<'a, T> {
+    pub fn ready(&self) -> Ready {
+        self.ready
+    }
+
+    pub fn get_inner(&self) -> &'a T {
+        self.fd.get_ref()
+    }
+
+    /// Forgets the readiness observed by this guard, the next `readable`/`writable` call waits
+    /// for the driver to report new readiness. Closed states are never cleared.
+    pub fn clear_ready(&mut self) {
+        let clear = (self.ready - Ready::closed()).as_usize();
+        self.fd
+            .cache(self.direction)
+            .fetch_and(!clear, Ordering::AcqRel);
+    }
+
+    /// Keeps the readiness for the next call, this is the default when the guard is dropped.
+    pub fn retain_ready(&mut self) {}
+
+    /// Runs `f`, clearing the readiness if it fails with `WouldBlock`.
+    pub fn try_io<R>(
+        &mut self,
+        f: impl FnOnce(&'a AsyncFd<T>) -> io::Result<R>,
+    ) -> Result<io::Result<R>, TryIoError> {
+        match f(self.fd) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.clear_ready();
+                Err(TryIoError(()))
+            }
+            res => Ok(res),
+        }
+    }
+}
+
+impl<T: AsRawFd> fmt::Debug for AsyncFdReadyGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncFdReadyGuard")
+            .field("direction", &self.direction)
+            .field("ready", &self.ready)
+            .finish()
+    }
+}
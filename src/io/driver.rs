@@ -1,17 +1,22 @@
 use std::{
-    collections::HashMap,
     io,
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        RwLock, {Arc, Weak},
+        atomic::{self, AtomicBool, AtomicUsize, Ordering},
+        Arc, Weak,
     },
     task::Waker,
 };
 
-use super::{Registration, Scheduled /* SuperSlab */};
+use super::{sys, Interest, Ready, Registration, Scheduled, SuperSlab};
 use crate::park::{Park, Unpark};
 
-use futures::task::AtomicWaker;
+/// The low bits of a token are the slab key of the resource, the following ones hold the
+/// generation of the slot so that events still queued for a dropped resource are not
+/// delivered to the one reusing its slot. Each slot bumps its generation when released, the
+/// top bit of a token is left clear so that it never matches the driver's own token.
+const KEY_MASK: usize = SuperSlab::<Scheduled>::MAX_ENTRIES - 1;
+const KEY_BITS: u32 = KEY_MASK.count_ones();
+const GENERATION_MASK: usize = (1 << (usize::BITS - KEY_BITS - 1)) - 1;
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum Direction {
@@ -20,9 +25,9 @@ pub enum Direction {
 }
 
 pub struct Driver {
-    events: mio::Events,
+    events: sys::Events,
+    selector: sys::Selector,
     inner: Arc<Inner>,
-    _self_registration: mio::Registration,
 }
 
 #[derive(Clone)]
@@ -31,39 +36,50 @@ pub struct Handle {
 }
 
 pub struct Inner {
-    io: mio::Poll,
-    map: RwLock<HashMap<mio::Token, Scheduled>>,
+    registry: sys::Registry,
+    map: SuperSlab<Scheduled>,
     n_sources: AtomicUsize,
-    self_wakeup: mio::SetReadiness,
+    is_shutdown: AtomicBool,
+    waker: sys::Waker,
 }
 
 impl Driver {
-    const TOKEN: mio::Token = mio::Token(666);
+    const TOKEN: usize = usize::MAX - 1;
 
     pub fn new() -> Self {
-        let (_self_registration, self_wakeup) = mio::Registration::new2();
-        let io = mio::Poll::new().expect("couldn't contruct mio::Poll");
+        Self::with_capacity(1024)
+    }
 
-        io.register(
-            &_self_registration,
-            Self::TOKEN,
-            mio::Ready::readable(),
-            mio::PollOpt::level(),
-        )
-        .unwrap();
+    /// `capacity` is the number of events handled per turn of the driver.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (selector, registry) = sys::selector().expect("couldn't construct the selector");
+        let waker = sys::Waker::new(&registry, Self::TOKEN).expect("couldn't create waker");
 
         Self {
-            events: mio::Events::with_capacity(1024),
+            events: sys::Events::with_capacity(capacity),
+            selector,
             inner: Arc::new(Inner {
-                map: RwLock::new(HashMap::new()),
+                registry,
+                map: SuperSlab::new(),
                 n_sources: AtomicUsize::new(0),
-                io,
-                self_wakeup,
+                is_shutdown: AtomicBool::new(false),
+                waker,
             }),
-            _self_registration,
         }
     }
-    #[allow(dead_code)]
+    /// Fails every registered resource: tasks waiting on one are woken up and polling its
+    /// readiness from now on returns a "runtime shutting down" error. New resources can no
+    /// longer be registered.
+    pub fn shutdown(&mut self) {
+        if self.inner.is_shutdown.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // Pairs with `add_io`: either it sees the flag or we see the slot it claimed.
+        atomic::fence(Ordering::SeqCst);
+        for sched in self.inner.map.iter().filter(|sched| !sched.is_released()) {
+            sched.shutdown();
+        }
+    }
     pub fn empty(&self) -> bool {
         self.inner.n_sources.load(Ordering::SeqCst) == 0
     }
@@ -72,82 +88,97 @@ impl Driver {
             inner: Arc::downgrade(&self.inner),
         }
     }
-    #[allow(dead_code)]
     pub fn register(
         &self,
-        token: mio::Token,
-        source: &dyn mio::Evented,
+        source: &mut dyn sys::Source,
+        interest: Interest,
     ) -> io::Result<Registration> {
-        Registration::new(self.handle(), token, source)
+        Registration::new(self.handle(), source, interest)
     }
     pub fn turn(&mut self, timeout: Option<std::time::Duration>) -> io::Result<()> {
-        let Inner { io, map, .. } = &*self.inner;
-
-        match io.poll(&mut self.events, timeout) {
+        match self.selector.select(&mut self.events, timeout) {
             Ok(_) => {}
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => return Ok(()),
             Err(e) => return Err(e),
         }
 
-        let mut ml = map.write().expect("couldn't access writer");
-        for event in self.events.iter() {
-            println!("{:?}", event);
-            self.dispatch(&mut ml, event);
+        for (token, ready) in self.events.iter() {
+            self.dispatch(token, ready);
         }
         Ok(())
     }
-    pub fn dispatch(&self, ml: &mut HashMap<mio::Token, Scheduled>, e: mio::Event) {
-        let token = e.token();
+    pub fn dispatch(&self, token: usize, kind: Ready) {
         if token == Driver::TOKEN {
-            self.inner
-                .self_wakeup
-                .set_readiness(mio::Ready::empty())
-                .unwrap();
+            // The waker only interrupts the poll, there is nothing to reset.
             return;
         }
-        let io = match ml.get_mut(&token) {
+        let io = match self.inner.map.get(token & KEY_MASK) {
             Some(io) => io,
             None => return,
         };
 
-        let kind = e.readiness();
-        io.set_readiness(|old| old | kind.as_usize());
+        // Events queued before the resource was dropped are ignored.
+        let generation = (token >> KEY_BITS) & GENERATION_MASK;
+        if io
+            .set_readiness_for(generation, |old| old | kind.as_usize())
+            .is_none()
+        {
+            return;
+        }
 
-        if kind.is_writable() || mio::unix::UnixReady::from(kind).is_hup() {
+        if !(kind & Direction::Write.mask()).is_empty() {
             io.writer.wake()
         }
-        if !(kind & (!mio::Ready::writable())).is_empty() {
+        if !(kind & Direction::Read.mask()).is_empty() {
             io.reader.wake()
         }
     }
 }
 
+impl Default for Driver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Inner {
-    pub fn add_io(&self, token: mio::Token, source: &dyn mio::Evented) {
-        let Self { io, map, .. } = &self;
-        map.write().unwrap().insert(
-            token.clone(),
-            Scheduled {
-                readiness: AtomicUsize::new(mio::Ready::empty().as_usize()),
-                reader: AtomicWaker::new(),
-                writer: AtomicWaker::new(),
-            },
-        );
-        let _ = io.register(source, token, mio::Ready::all(), mio::PollOpt::edge());
+    pub fn add_io(&self, source: &mut dyn sys::Source, interest: Interest) -> io::Result<usize> {
+        if self.is_shutdown.load(Ordering::Acquire) {
+            return Err(shutdown_error());
+        }
+        let Self { registry, map, .. } = &self;
+        let (key, sched) = map
+            .alloc()
+            .ok_or_else(|| io::Error::other("reached maximum number of IO sources"))?;
+        let generation = sched.reset();
+        let token = generation << KEY_BITS | key;
+
+        // A shutdown that went over the slots before it was reset missed it.
+        atomic::fence(Ordering::SeqCst);
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            sched.release(next_generation(generation));
+            map.release(key);
+            return Err(shutdown_error());
+        }
+
+        if let Err(e) = registry.register(source, token, interest) {
+            sched.release(next_generation(generation));
+            map.release(key);
+            return Err(e);
+        }
         self.n_sources.fetch_add(1, Ordering::SeqCst);
+        Ok(token)
     }
 
-    pub fn register(&self, token: mio::Token, dir: Direction, w: &Waker) {
-        let rl = self.map.read().expect("couldn't acquire read access");
-
-        let sched = rl
-            .get(&token)
-            .unwrap_or_else(|| panic!("IO resource for token {:?} does not exist!", token));
+    pub fn register(&self, token: usize, dir: Direction, w: &Waker) {
+        let sched = lookup(&self.map, token)
+            .unwrap_or_else(|| panic!("IO resource for token {} does not exist!", token));
 
         let readiness = sched.get_readiness();
 
         let (waker, ready) = match dir {
-            Direction::Read => (&sched.reader, !mio::Ready::writable()),
-            Direction::Write => (&sched.writer, mio::Ready::writable()),
+            Direction::Read => (&sched.reader, Direction::Read.mask()),
+            Direction::Write => (&sched.writer, Direction::Write.mask()),
         };
 
         waker.register(w);
@@ -157,22 +188,50 @@ impl Inner {
         }
     }
 
-    pub fn deregister_source(&self, source: &dyn mio::Evented) -> io::Result<()> {
-        self.io.deregister(source)
+    pub fn reregister_source(
+        &self,
+        source: &mut dyn sys::Source,
+        token: usize,
+        interest: Interest,
+    ) -> io::Result<()> {
+        self.registry.reregister(source, token, interest)
+    }
+
+    pub fn deregister_source(&self, source: &mut dyn sys::Source) -> io::Result<()> {
+        self.registry.deregister(source)
+    }
+
+    pub fn drop_source(&self, token: &usize) {
+        if let Some(sched) = lookup(&self.map, *token) {
+            sched.release(next_generation(sched.generation()));
+            self.map.release(*token & KEY_MASK);
+            self.n_sources.fetch_sub(1, Ordering::SeqCst);
+        }
     }
 
-    pub fn drop_source(&self, token: &mio::Token) {
-        drop(
-            self.map
-                .write()
-                .expect("couldn't access write map ressource")
-                .remove(token),
-        );
-        self.n_sources.fetch_sub(1, Ordering::SeqCst);
+    pub(super) fn lookup(&self, token: usize) -> Option<&Scheduled> {
+        lookup(&self.map, token)
     }
+}
 
-    pub fn read_map(&self) -> std::sync::RwLockReadGuard<HashMap<mio::Token, Scheduled>> {
-        self.map.read().expect("couldn't access the map")
+/// Error returned for resources of a driver that has been shut down.
+pub(super) fn shutdown_error() -> io::Error {
+    io::Error::other("runtime shutting down")
+}
+
+fn next_generation(generation: usize) -> usize {
+    generation.wrapping_add(1) & GENERATION_MASK
+}
+
+/// Finds the resource registered with `token`, unless its slot was released since.
+fn lookup(map: &SuperSlab<Scheduled>, token: usize) -> Option<&Scheduled> {
+    map.get(token & KEY_MASK)
+        .filter(|sched| sched.generation() == (token >> KEY_BITS) & GENERATION_MASK)
+}
+
+impl Drop for Driver {
+    fn drop(&mut self) {
+        self.shutdown();
     }
 }
 
@@ -198,10 +257,9 @@ impl Handle {
 
     fn wake(&self) {
         if let Some(inner) = self.inner() {
-            inner
-                .self_wakeup
-                .set_readiness(mio::Ready::readable())
-                .unwrap();
+            // A failed wake-up leaves the driver parked until its next event, which beats
+            // panicking in whoever unparks it.
+            let _ = inner.waker.wake();
         }
     }
 }
@@ -213,10 +271,88 @@ impl Unpark for Handle {
 }
 
 impl Direction {
-    pub fn mask(self) -> mio::Ready {
+    pub fn mask(self) -> Ready {
         match self {
-            Self::Read => mio::Ready::all() - mio::Ready::writable(),
-            Self::Write => mio::Ready::writable() | mio::unix::UnixReady::hup(),
+            Self::Read => {
+                Ready::readable() | Ready::priority() | Ready::read_closed() | Ready::error()
+            }
+            Self::Write => Ready::writable() | Ready::write_closed() | Ready::error(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+    use std::os::unix::io::AsRawFd;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+    use std::thread;
+    use std::time::Duration;
+
+    use super::Driver;
+    use crate::io::{sys, Interest, Registration};
+
+    fn register(handle: &super::Handle, socket: &UdpSocket) -> Registration {
+        let fd = socket.as_raw_fd();
+        let mut source = sys::fd_source(&fd);
+        Registration::new(handle.clone(), &mut source, Interest::readable()).unwrap()
+    }
+
+    /// Sockets are closed while their events are in flight, the slot reusing their key must
+    /// never see them.
+    #[test]
+    fn stale_events_skip_reused_slots() {
+        let mut driver = Driver::new();
+        let handle = driver.handle();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let turning = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Acquire) {
+                    driver.turn(Some(Duration::from_millis(1))).unwrap();
+                }
+                driver
+            })
+        };
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for _ in 0..2000 {
+            let noisy = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let registration = register(&handle, &noisy);
+            sender
+                .send_to(b"ping", noisy.local_addr().unwrap())
+                .unwrap();
+            thread::yield_now();
+            drop(registration);
+            drop(noisy);
+
+            let quiet = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let registration = register(&handle, &quiet);
+            thread::yield_now();
+            assert_eq!(registration.take_read_ready().unwrap(), None);
+        }
+
+        stop.store(true, Ordering::Release);
+        let driver = turning.join().unwrap();
+        assert!(driver.empty());
+    }
+
+    #[test]
+    fn shutdown_fails_registered_and_new_resources() {
+        let mut driver = Driver::new();
+        let handle = driver.handle();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let registration = register(&handle, &socket);
+
+        driver.shutdown();
+        assert!(registration.take_read_ready().is_err());
+
+        let fd = socket.as_raw_fd();
+        let mut source = sys::fd_source(&fd);
+        assert!(Registration::new(handle, &mut source, Interest::readable()).is_err());
+    }
+}
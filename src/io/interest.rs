@@ -0,0 +1,81 @@
+use std::{fmt, ops};
+
+use super::Ready;
+
+const READABLE: u8 = 0b001;
+const WRITABLE: u8 = 0b010;
+const PRIORITY: u8 = 0b100;
+
+/// Readiness a resource is registered for, a resource is never woken up for the rest.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Interest(u8);
+
+impl Interest {
+    pub fn readable() -> Self {
+        Self(READABLE)
+    }
+    pub fn writable() -> Self {
+        Self(WRITABLE)
+    }
+    pub fn both() -> Self {
+        Self(READABLE | WRITABLE)
+    }
+    /// Out-of-band data, such as TCP urgent data. Only Linux and Android report it.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn priority() -> Self {
+        Self(PRIORITY)
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub fn is_readable(self) -> bool {
+        self.0 & READABLE != 0
+    }
+    pub fn is_writable(self) -> bool {
+        self.0 & WRITABLE != 0
+    }
+    pub fn is_priority(self) -> bool {
+        self.0 & PRIORITY != 0
+    }
+
+    /// Readiness that can be reported for a resource registered with this interest, closed
+    /// and error states are always reported.
+    pub fn mask(self) -> Ready {
+        let mut mask = Ready::closed() | Ready::error();
+        if self.is_readable() {
+            mask |= Ready::readable();
+        }
+        if self.is_writable() {
+            mask |= Ready::writable();
+        }
+        if self.is_priority() {
+            mask |= Ready::priority();
+        }
+        mask
+    }
+}
+
+impl ops::BitOr for Interest {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl fmt::Debug for Interest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (READABLE, "Readable"),
+            (WRITABLE, "Writable"),
+            (PRIORITY, "Priority"),
+        ];
+        let mut set = f.debug_set();
+        for &(bit, name) in flags.iter() {
+            if self.0 & bit != 0 {
+                set.entry(&format_args!("{}", name));
+            }
+        }
+        set.finish()
+    }
+}
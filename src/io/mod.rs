@@ -1,11 +1,19 @@
+mod async_fd;
 mod driver;
+mod interest;
 mod poll_evented;
+mod ready;
 mod registration;
 mod scheduled;
-// mod superslab;
+mod superslab;
+mod sys;
 
+pub use async_fd::{AsyncFd, AsyncFdReadyGuard, TryIoError};
 pub use driver::{Direction, Driver, Handle};
+pub use interest::Interest;
 pub use poll_evented::PollEvented;
+pub use ready::Ready;
 pub use registration::Registration;
 pub use scheduled::Scheduled;
-// pub use superslab::SuperSlab;
+pub use superslab::SuperSlab;
+pub use sys::{fd_source, Source};
@@ -3,14 +3,14 @@ use std::pin::Pin;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::{Context, Poll};
 
-use super::{Handle, Registration};
+use super::{Handle, Interest, Ready, Registration, Source};
 
 use futures::{
     io::{AsyncRead, AsyncWrite},
     ready,
 };
 
-pub struct PollEvented<E: mio::Evented> {
+pub struct PollEvented<E: Source> {
     io: Option<E>,
     inner: Inner,
 }
@@ -25,10 +25,10 @@ macro_rules! poll_ready {
     ($me:expr, $mask:expr, $cache:ident, $take:ident, $poll:expr) => {{
         // Load cached & encoded readiness.
         let mut cached = $me.inner.$cache.load(Ordering::Relaxed);
-        let mask = $mask | mio::unix::UnixReady::hup();
+        let mask = $mask | Ready::closed();
 
         // See if the current readiness matches any bits.
-        let mut ret = mio::Ready::from_usize(cached) & $mask;
+        let mut ret = Ready::from_usize(cached) & $mask;
 
         if ret.is_empty() {
             // Readiness does not match, consume the registration's readiness
@@ -58,14 +58,20 @@ macro_rules! poll_ready {
                 $me.inner.$cache.store(cached, Ordering::Relaxed);
             }
 
-            Poll::Ready(Ok(mio::Ready::from_usize(cached)))
+            Poll::Ready(Ok(Ready::from_usize(cached)))
         }
     }};
 }
 
-impl<E: mio::Evented> PollEvented<E> {
-    pub fn new(handle: Handle, token: mio::Token, io: E) -> io::Result<Self> {
-        let registration = Registration::new(handle, token, &io)?;
+impl<E: Source> PollEvented<E> {
+    /// Registers `io` for reading only, writing is added to its interest the first time the
+    /// `AsyncWrite` implementation is used.
+    pub fn new(handle: Handle, io: E) -> io::Result<Self> {
+        Self::new_with_interest(handle, io, Interest::readable())
+    }
+
+    pub fn new_with_interest(handle: Handle, mut io: E, interest: Interest) -> io::Result<Self> {
+        let registration = Registration::new(handle, &mut io, interest)?;
         Ok(Self {
             io: Some(io),
             inner: Inner {
@@ -76,6 +82,16 @@ impl<E: mio::Evented> PollEvented<E> {
         })
     }
 
+    /// Extends the interest of the resource, re-registering it if needed.
+    pub fn add_interest(&mut self, interest: Interest) -> io::Result<()> {
+        let current = self.inner.registration.interest();
+        if current.contains(interest) {
+            return Ok(());
+        }
+        let io = self.io.as_mut().unwrap();
+        self.inner.registration.reregister(io, current | interest)
+    }
+
     pub fn get_mut(&mut self) -> &mut E {
         self.io.as_mut().unwrap()
     }
@@ -83,11 +99,14 @@ impl<E: mio::Evented> PollEvented<E> {
         self.io.as_ref().unwrap()
     }
 
-    pub fn poll_read_ready(
-        &self,
-        cx: &mut Context<'_>,
-        mask: mio::Ready,
-    ) -> Poll<io::Result<mio::Ready>> {
+    /// Deregisters the resource from the driver and gives it back.
+    pub fn into_inner(mut self) -> io::Result<E> {
+        let mut io = self.io.take().unwrap();
+        self.inner.registration.deregister(&mut io)?;
+        Ok(io)
+    }
+
+    pub fn poll_read_ready(&self, cx: &mut Context<'_>, mask: Ready) -> Poll<io::Result<Ready>> {
         assert!(!mask.is_writable(), "cannot poll for write readiness");
         poll_ready!(
             self,
@@ -97,12 +116,12 @@ impl<E: mio::Evented> PollEvented<E> {
             self.inner.registration.poll_read_ready(cx)
         )
     }
-    pub fn clear_read_ready(&self, cx: &mut Context<'_>, ready: mio::Ready) -> io::Result<()> {
+    pub fn clear_read_ready(&self, cx: &mut Context<'_>, ready: Ready) -> io::Result<()> {
         // Cannot clear write readiness
         assert!(!ready.is_writable(), "cannot clear write readiness");
         assert!(
-            !mio::unix::UnixReady::from(ready).is_hup(),
-            "cannot clear HUP readiness"
+            (ready & Ready::closed()).is_empty(),
+            "cannot clear closed readiness"
         );
 
         self.inner
@@ -116,10 +135,10 @@ impl<E: mio::Evented> PollEvented<E> {
         Ok(())
     }
 
-    pub fn poll_write_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<mio::Ready>> {
+    pub fn poll_write_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<Ready>> {
         poll_ready!(
             self,
-            mio::Ready::writable(),
+            Ready::writable(),
             write_readiness,
             take_write_ready,
             self.inner.registration.poll_write_ready(cx)
@@ -128,7 +147,7 @@ impl<E: mio::Evented> PollEvented<E> {
     pub fn clear_write_ready(&self, cx: &mut Context<'_>) -> io::Result<()> {
         self.inner
             .write_readiness
-            .fetch_and(!mio::Ready::writable().as_usize(), Ordering::Relaxed);
+            .fetch_and(!Ready::writable().as_usize(), Ordering::Relaxed);
 
         if self.poll_write_ready(cx)?.is_ready() {
             cx.waker().wake_by_ref();
@@ -140,20 +159,20 @@ impl<E: mio::Evented> PollEvented<E> {
 
 impl<E> AsyncRead for PollEvented<E>
 where
-    E: mio::Evented + std::io::Read + std::marker::Unpin,
+    E: Source + std::io::Read + std::marker::Unpin,
 {
     fn poll_read(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
-        println!("evented poll_read");
-        ready!(self.poll_read_ready(cx, mio::Ready::readable()))?;
+        self.add_interest(Interest::readable())?;
+        ready!(self.poll_read_ready(cx, Ready::readable()))?;
 
         let r = (*self).get_mut().read(buf);
 
         if is_wouldblock(&r) {
-            self.clear_read_ready(cx, mio::Ready::readable())?;
+            self.clear_read_ready(cx, Ready::readable())?;
             return Poll::Pending;
         }
         Poll::Ready(r)
@@ -162,13 +181,14 @@ where
 
 impl<E> AsyncWrite for PollEvented<E>
 where
-    E: mio::Evented + std::io::Write + std::marker::Unpin,
+    E: Source + std::io::Write + std::marker::Unpin,
 {
     fn poll_write(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
+        self.add_interest(Interest::writable())?;
         ready!(self.poll_write_ready(cx))?;
 
         let r = (*self).get_mut().write(buf);
@@ -182,6 +202,7 @@ where
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.add_interest(Interest::writable())?;
         ready!(self.poll_write_ready(cx))?;
 
         let r = (*self).get_mut().flush();
@@ -206,10 +227,10 @@ fn is_wouldblock<T>(r: &io::Result<T>) -> bool {
     }
 }
 
-impl<E: mio::Evented> Drop for PollEvented<E> {
+impl<E: Source> Drop for PollEvented<E> {
     fn drop(&mut self) {
-        if let Some(io) = self.io.take() {
-            let _ = self.inner.registration.deregister(&io);
+        if let Some(mut io) = self.io.take() {
+            let _ = self.inner.registration.deregister(&mut io);
         }
     }
 }
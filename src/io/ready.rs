@@ -0,0 +1,125 @@
+use std::{fmt, ops};
+
+const READABLE: usize = 0b0_0001;
+const WRITABLE: usize = 0b0_0010;
+const READ_CLOSED: usize = 0b0_0100;
+const WRITE_CLOSED: usize = 0b0_1000;
+const ERROR: usize = 0b1_0000;
+const PRIORITY: usize = 0b10_0000;
+
+/// Readiness of an IO resource, as reported by the driver.
+///
+/// Each backend translates its own events to this encoding, which is what gets stored in the
+/// `Scheduled` slots and cached in `PollEvented`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Ready(usize);
+
+impl Ready {
+    pub fn empty() -> Self {
+        Self(0)
+    }
+    pub fn readable() -> Self {
+        Self(READABLE)
+    }
+    pub fn writable() -> Self {
+        Self(WRITABLE)
+    }
+    pub fn read_closed() -> Self {
+        Self(READ_CLOSED)
+    }
+    pub fn write_closed() -> Self {
+        Self(WRITE_CLOSED)
+    }
+    pub fn error() -> Self {
+        Self(ERROR)
+    }
+    pub fn priority() -> Self {
+        Self(PRIORITY)
+    }
+    /// Both closed states, they stay set once observed.
+    pub fn closed() -> Self {
+        Self(READ_CLOSED | WRITE_CLOSED)
+    }
+    pub fn all() -> Self {
+        Self(READABLE | WRITABLE | READ_CLOSED | WRITE_CLOSED | ERROR | PRIORITY)
+    }
+
+    pub fn from_usize(bits: usize) -> Self {
+        Self(bits & Self::all().0)
+    }
+    pub fn as_usize(self) -> usize {
+        self.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub fn is_readable(self) -> bool {
+        self.0 & READABLE != 0
+    }
+    pub fn is_writable(self) -> bool {
+        self.0 & WRITABLE != 0
+    }
+    pub fn is_read_closed(self) -> bool {
+        self.0 & READ_CLOSED != 0
+    }
+    pub fn is_write_closed(self) -> bool {
+        self.0 & WRITE_CLOSED != 0
+    }
+    pub fn is_error(self) -> bool {
+        self.0 & ERROR != 0
+    }
+    pub fn is_priority(self) -> bool {
+        self.0 & PRIORITY != 0
+    }
+}
+
+impl ops::BitOr for Ready {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl ops::BitOrAssign for Ready {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0
+    }
+}
+
+impl ops::BitAnd for Ready {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl ops::Sub for Ready {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl fmt::Debug for Ready {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (READABLE, "Readable"),
+            (WRITABLE, "Writable"),
+            (READ_CLOSED, "ReadClosed"),
+            (WRITE_CLOSED, "WriteClosed"),
+            (ERROR, "Error"),
+            (PRIORITY, "Priority"),
+        ];
+        let mut set = f.debug_set();
+        for &(bit, name) in flags.iter() {
+            if self.0 & bit != 0 {
+                set.entry(&format_args!("{}", name));
+            }
+        }
+        set.finish()
+    }
+}
@@ -3,43 +3,66 @@ use std::{
     task::{Context, Poll},
 };
 
-use super::{Direction, Handle};
+use super::{driver, sys, Direction, Handle, Interest, Ready};
 
 pub struct Registration {
     handle: Handle,
-    token: mio::Token,
+    token: usize,
+    interest: Interest,
 }
 
 impl Registration {
-    pub fn new(handle: Handle, token: mio::Token, io: &dyn mio::Evented) -> io::Result<Self> {
+    pub fn new(handle: Handle, io: &mut dyn sys::Source, interest: Interest) -> io::Result<Self> {
         let inner = match handle.inner() {
             Some(inner) => inner,
-            None => return Err(io::Error::new(io::ErrorKind::Other, "driver gone")),
+            None => return Err(io::Error::other("driver gone")),
         };
-        inner.add_io(token, io);
-        Ok(Self { handle, token })
+        let token = inner.add_io(io, interest)?;
+        Ok(Self {
+            handle,
+            token,
+            interest,
+        })
     }
-    pub fn deregister(&mut self, io: &dyn mio::Evented) -> io::Result<()> {
+    /// Replaces the interest of the resource, readiness outside of the new interest is no
+    /// longer reported.
+    pub fn reregister(&mut self, io: &mut dyn sys::Source, interest: Interest) -> io::Result<()> {
         let inner = match self.handle.inner() {
             Some(inner) => inner,
-            None => return Err(io::Error::new(io::ErrorKind::Other, "reactor gone")),
+            None => return Err(io::Error::other("reactor gone")),
+        };
+        inner.reregister_source(io, self.token, interest)?;
+        self.interest = interest;
+        Ok(())
+    }
+    pub fn interest(&self) -> Interest {
+        self.interest
+    }
+    /// Token under which the driver dispatches the events of the resource.
+    pub fn token(&self) -> usize {
+        self.token
+    }
+    pub fn deregister(&mut self, io: &mut dyn sys::Source) -> io::Result<()> {
+        let inner = match self.handle.inner() {
+            Some(inner) => inner,
+            None => return Err(io::Error::other("reactor gone")),
         };
         inner.deregister_source(io)
     }
 
-    pub fn poll_read_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<mio::Ready>> {
+    pub fn poll_read_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<Ready>> {
         self.poll_ready(Direction::Read, Some(cx))?.map(Ok)
     }
-    pub fn poll_write_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<mio::Ready>> {
+    pub fn poll_write_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<Ready>> {
         self.poll_ready(Direction::Write, Some(cx))?.map(Ok)
     }
-    pub fn take_write_ready(&self) -> io::Result<Option<mio::Ready>> {
+    pub fn take_write_ready(&self) -> io::Result<Option<Ready>> {
         match self.poll_ready(Direction::Write, None)? {
             Poll::Ready(ready) => Ok(Some(ready)),
             Poll::Pending => Ok(None),
         }
     }
-    pub fn take_read_ready(&self) -> io::Result<Option<mio::Ready>> {
+    pub fn take_read_ready(&self) -> io::Result<Option<Ready>> {
         match self.poll_ready(Direction::Read, None)? {
             Poll::Ready(ready) => Ok(Some(ready)),
             Poll::Pending => Ok(None),
@@ -50,10 +73,10 @@ impl Registration {
         &self,
         direction: Direction,
         cx: Option<&mut Context<'_>>,
-    ) -> io::Result<Poll<mio::Ready>> {
+    ) -> io::Result<Poll<Ready>> {
         let inner = match self.handle.inner() {
             Some(inner) => inner,
-            None => return Err(io::Error::new(io::ErrorKind::Other, "reactor gone")),
+            None => return Err(driver::shutdown_error()),
         };
 
         if let Some(ref cx) = cx {
@@ -61,16 +84,20 @@ impl Registration {
         }
 
         let mask = direction.mask();
-        let mask_no_hup = (mask - mio::unix::UnixReady::hup()).as_usize();
+        // Closed states are final, they are never consumed.
+        let mask_no_hup = (mask - Ready::closed()).as_usize();
 
-        let rl = inner.read_map();
-        let sched = match rl.get(&self.token) {
+        let sched = match inner.lookup(self.token) {
             Some(shed) => shed,
-            None => return Err(io::Error::new(io::ErrorKind::Other, "token not found")),
+            None => return Err(io::Error::other("token not found")),
         };
+        // Checked after registering the waker so that a concurrent shutdown is not missed.
+        if sched.is_shutdown() {
+            return Err(driver::shutdown_error());
+        }
 
         let curr_ready = sched.set_readiness(|curr| curr & (!mask_no_hup));
-        let mut ready = mask & mio::Ready::from_usize(curr_ready);
+        let mut ready = mask & Ready::from_usize(curr_ready);
 
         if ready.is_empty() {
             if let Some(cx) = cx {
@@ -81,7 +108,7 @@ impl Registration {
                 }
                 // Try again
                 let curr_ready = sched.set_readiness(|curr| curr & (!mask_no_hup));
-                ready = mask & mio::Ready::from_usize(curr_ready);
+                ready = mask & Ready::from_usize(curr_ready);
             }
         }
 
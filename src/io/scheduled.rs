@@ -2,6 +2,16 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 
 use futures::task::AtomicWaker;
 
+use super::Ready;
+
+/// `Ready` only uses the low bits of the word, the bits above hold the shutdown and released
+/// flags and the generation of the slot so that readiness and generation are always updated
+/// together.
+const READINESS_MASK: usize = (1 << 15) - 1;
+const SHUTDOWN: usize = 1 << 15;
+const RELEASED: usize = 1 << 16;
+const GENERATION_SHIFT: u32 = 17;
+
 pub struct Scheduled {
     pub readiness: AtomicUsize,
     pub reader: AtomicWaker,
@@ -10,29 +20,86 @@ pub struct Scheduled {
 
 impl Scheduled {
     pub fn get_readiness(&self) -> usize {
-        self.readiness.load(Ordering::Acquire)
+        self.readiness.load(Ordering::Acquire) & READINESS_MASK
+    }
+    pub fn generation(&self) -> usize {
+        self.readiness.load(Ordering::Acquire) >> GENERATION_SHIFT
+    }
+    pub fn is_shutdown(&self) -> bool {
+        self.readiness.load(Ordering::Acquire) & SHUTDOWN != 0
+    }
+    pub fn is_released(&self) -> bool {
+        self.readiness.load(Ordering::Acquire) & RELEASED != 0
+    }
+    /// Marks the resource as unusable and wakes up whoever is waiting on it.
+    pub fn shutdown(&self) {
+        self.readiness.fetch_or(SHUTDOWN, Ordering::AcqRel);
+        self.reader.wake();
+        self.writer.wake();
     }
     pub fn set_readiness(&self, f: impl Fn(usize) -> usize) -> usize {
         let mut current = self.readiness.load(Ordering::Acquire);
         loop {
-            let current_readiness = current & mio::Ready::all().as_usize();
-            let new = f(current_readiness);
+            let current_readiness = current & Ready::all().as_usize();
+            let new = (current & !READINESS_MASK) | (f(current_readiness) & READINESS_MASK);
+
+            match self
+                .readiness
+                .compare_exchange(current, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return current & READINESS_MASK,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+    /// Same as `set_readiness` but leaves the readiness untouched and returns `None` if the
+    /// slot no longer belongs to `generation`.
+    pub fn set_readiness_for(
+        &self,
+        generation: usize,
+        f: impl Fn(usize) -> usize,
+    ) -> Option<usize> {
+        let mut current = self.readiness.load(Ordering::Acquire);
+        loop {
+            if current >> GENERATION_SHIFT != generation {
+                return None;
+            }
+            let current_readiness = current & Ready::all().as_usize();
+            let new = (current & !READINESS_MASK) | (f(current_readiness) & READINESS_MASK);
 
             match self
                 .readiness
                 .compare_exchange(current, new, Ordering::AcqRel, Ordering::Acquire)
             {
-                Ok(_) => return current,
+                Ok(_) => return Some(current & READINESS_MASK),
                 Err(actual) => current = actual,
             }
         }
     }
+    /// Hands the slot over to a new resource with no readiness and no wakers, returns the
+    /// generation its token must carry.
+    pub fn reset(&self) -> usize {
+        let generation = self.generation();
+        self.readiness
+            .store(generation << GENERATION_SHIFT, Ordering::Release);
+        drop(self.reader.take());
+        drop(self.writer.take());
+        generation
+    }
+    /// Marks the slot as free and moves it to `generation`, so that the tokens of its previous
+    /// resource no longer match it.
+    pub fn release(&self, generation: usize) {
+        self.readiness
+            .store(RELEASED | generation << GENERATION_SHIFT, Ordering::Release);
+        drop(self.reader.take());
+        drop(self.writer.take());
+    }
 }
 
 impl Default for Scheduled {
     fn default() -> Self {
         Self {
-            readiness: AtomicUsize::new(mio::Ready::empty().as_usize()),
+            readiness: AtomicUsize::new(RELEASED),
             reader: AtomicWaker::new(),
             writer: AtomicWaker::new(),
         }
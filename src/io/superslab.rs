@@ -0,0 +1,123 @@
+use std::ptr;
+use std::sync::{
+    atomic::{AtomicPtr, Ordering},
+    Mutex,
+};
+
+/// Size of the first page, every following page is twice as large as the previous one.
+const INITIAL_PAGE_SIZE: usize = 32;
+const PAGES: usize = 20;
+
+/// Storage for the IO resources of a driver, the key of an entry is used as its token.
+///
+/// Slots live in pages that are allocated on demand and only freed with the slab, so looking
+/// up a slot never takes a lock: only allocating and releasing keys go through a mutex. A
+/// released slot is reused in place, it is up to `T` to tell stale users apart.
+pub struct SuperSlab<T> {
+    pages: [AtomicPtr<T>; PAGES],
+    keys: Mutex<Keys>,
+}
+
+struct Keys {
+    free: Vec<usize>,
+    next: usize,
+}
+
+impl<T: Default> SuperSlab<T> {
+    /// Keys stay below this bound so that they never collide with the driver's own token.
+    pub const MAX_ENTRIES: usize = 1 << 24;
+
+    pub fn new() -> Self {
+        Self {
+            pages: Default::default(),
+            keys: Mutex::new(Keys {
+                free: Vec::new(),
+                next: 0,
+            }),
+        }
+    }
+
+    /// Reserves a slot, the returned reference still holds whatever the previous user of
+    /// the slot left in it.
+    pub fn alloc(&self) -> Option<(usize, &T)> {
+        let mut keys = self.keys.lock().unwrap();
+        let key = match keys.free.pop() {
+            Some(key) => key,
+            None if keys.next < Self::MAX_ENTRIES => {
+                keys.next += 1;
+                keys.next - 1
+            }
+            None => return None,
+        };
+
+        let (page, _) = page_of(key);
+        if self.pages[page].load(Ordering::Acquire).is_null() {
+            let slots: Box<[T]> = (0..page_size(page)).map(|_| T::default()).collect();
+            let slots = Box::into_raw(slots) as *mut T;
+            self.pages[page].store(slots, Ordering::Release);
+        }
+        drop(keys);
+
+        self.get(key).map(|slot| (key, slot))
+    }
+
+    pub fn release(&self, key: usize) {
+        self.keys.lock().unwrap().free.push(key);
+    }
+
+    pub fn get(&self, key: usize) -> Option<&T> {
+        if key >= Self::MAX_ENTRIES {
+            return None;
+        }
+        let (page, offset) = page_of(key);
+        let slots = self.pages[page].load(Ordering::Acquire);
+        if slots.is_null() {
+            return None;
+        }
+        // Pages are never freed while `self` is alive.
+        Some(unsafe { &*slots.add(offset) })
+    }
+
+    /// Every slot allocated so far, used or not.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..PAGES).flat_map(move |page| {
+            let slots = self.pages[page].load(Ordering::Acquire);
+            let len = if slots.is_null() { 0 } else { page_size(page) };
+            (0..len).map(move |offset| unsafe { &*slots.add(offset) })
+        })
+    }
+}
+
+impl<T: Default> Default for SuperSlab<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for SuperSlab<T> {
+    fn drop(&mut self) {
+        for (page, slots) in self.pages.iter_mut().enumerate() {
+            let slots = slots.swap(ptr::null_mut(), Ordering::AcqRel);
+            if !slots.is_null() {
+                let slots = ptr::slice_from_raw_parts_mut(slots, page_size(page));
+                drop(unsafe { Box::from_raw(slots) });
+            }
+        }
+    }
+}
+
+unsafe impl<T: Send + Sync> Send for SuperSlab<T> {}
+unsafe impl<T: Send + Sync> Sync for SuperSlab<T> {}
+
+fn page_size(page: usize) -> usize {
+    INITIAL_PAGE_SIZE << page
+}
+
+/// Page holding `key` and the offset of the slot in that page.
+fn page_of(key: usize) -> (usize, usize) {
+    let shifted = (key + INITIAL_PAGE_SIZE) / INITIAL_PAGE_SIZE;
+    let bits = (std::mem::size_of::<usize>() * 8) as u32;
+    let page = (bits - shifted.leading_zeros() - 1) as usize;
+    let page_start = INITIAL_PAGE_SIZE * ((1 << page) - 1);
+    (page, key - page_start)
+}
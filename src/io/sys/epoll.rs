@@ -0,0 +1,200 @@
+use std::{
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::io::{Interest, Ready};
+
+/// IO resources that can be registered with the driver.
+pub trait Source: AsRawFd {}
+
+impl<T: AsRawFd + ?Sized> Source for T {}
+
+/// Source for a raw file descriptor owned by the caller.
+pub fn fd_source(fd: &RawFd) -> impl Source + '_ {
+    *fd
+}
+
+/// Owned file descriptor, closed on drop.
+struct Fd(RawFd);
+
+pub struct Selector {
+    epfd: Arc<Fd>,
+}
+
+pub struct Registry {
+    epfd: Arc<Fd>,
+}
+
+/// Wakes the selector by writing to an eventfd registered in edge-triggered mode, every
+/// write is a new edge so the counter never needs to be drained.
+pub struct Waker {
+    fd: Fd,
+}
+
+pub struct Events {
+    events: Vec<libc::epoll_event>,
+}
+
+/// Creates a selector and the registry used to add resources to it from any thread.
+pub fn selector() -> io::Result<(Selector, Registry)> {
+    let epfd = Arc::new(Fd(syscall(unsafe {
+        libc::epoll_create1(libc::EPOLL_CLOEXEC)
+    })?));
+    Ok((Selector { epfd: epfd.clone() }, Registry { epfd }))
+}
+
+impl Selector {
+    pub fn select(&mut self, events: &mut Events, timeout: Option<Duration>) -> io::Result<()> {
+        let timeout = timeout
+            .map(|to| {
+                // Round up so that a sub-millisecond timeout does not turn into a busy loop.
+                let ms = to.as_millis() + u128::from(to.subsec_nanos() % 1_000_000 != 0);
+                ms.min(libc::c_int::MAX as u128) as libc::c_int
+            })
+            .unwrap_or(-1);
+
+        events.events.clear();
+        let n = syscall(unsafe {
+            libc::epoll_wait(
+                self.epfd.0,
+                events.events.as_mut_ptr(),
+                events.events.capacity() as libc::c_int,
+                timeout,
+            )
+        })?;
+        unsafe { events.events.set_len(n as usize) };
+        Ok(())
+    }
+}
+
+impl Registry {
+    pub fn register(
+        &self,
+        source: &mut dyn Source,
+        token: usize,
+        interest: Interest,
+    ) -> io::Result<()> {
+        let flags = to_epoll(interest);
+        self.ctl(libc::EPOLL_CTL_ADD, source.as_raw_fd(), flags, token)
+    }
+    pub fn reregister(
+        &self,
+        source: &mut dyn Source,
+        token: usize,
+        interest: Interest,
+    ) -> io::Result<()> {
+        let flags = to_epoll(interest);
+        self.ctl(libc::EPOLL_CTL_MOD, source.as_raw_fd(), flags, token)
+    }
+    pub fn deregister(&self, source: &mut dyn Source) -> io::Result<()> {
+        self.ctl(libc::EPOLL_CTL_DEL, source.as_raw_fd(), 0, 0)
+    }
+
+    fn ctl(&self, op: libc::c_int, fd: RawFd, flags: libc::c_int, token: usize) -> io::Result<()> {
+        let mut event = libc::epoll_event {
+            events: flags as u32,
+            u64: token as u64,
+        };
+        syscall(unsafe { libc::epoll_ctl(self.epfd.0, op, fd, &mut event) })?;
+        Ok(())
+    }
+}
+
+impl Waker {
+    pub fn new(registry: &Registry, token: usize) -> io::Result<Self> {
+        let fd = Fd(syscall(unsafe {
+            libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK)
+        })?);
+        registry.ctl(
+            libc::EPOLL_CTL_ADD,
+            fd.0,
+            libc::EPOLLIN | libc::EPOLLET,
+            token,
+        )?;
+        Ok(Self { fd })
+    }
+    pub fn wake(&self) -> io::Result<()> {
+        let one = 1u64.to_ne_bytes();
+        match syscall(unsafe { libc::write(self.fd.0, one.as_ptr() as *const _, one.len()) as _ }) {
+            Ok(_) => Ok(()),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                // The counter is full, reset it and try again.
+                let mut buf = [0u8; 8];
+                let _ = unsafe { libc::read(self.fd.0, buf.as_mut_ptr() as *mut _, buf.len()) };
+                self.wake()
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Events {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: Vec::with_capacity(capacity),
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Ready)> + '_ {
+        self.events.iter().map(|event| {
+            // `epoll_event` is packed, copy the fields out before using them.
+            let (token, flags) = (event.u64, event.events as libc::c_int);
+            (token as usize, ready(flags))
+        })
+    }
+}
+
+fn to_epoll(interest: Interest) -> libc::c_int {
+    let mut flags = libc::EPOLLET;
+    if interest.is_readable() {
+        flags |= libc::EPOLLIN | libc::EPOLLRDHUP;
+    }
+    if interest.is_writable() {
+        flags |= libc::EPOLLOUT;
+    }
+    if interest.is_priority() {
+        flags |= libc::EPOLLPRI;
+    }
+    flags
+}
+
+fn ready(flags: libc::c_int) -> Ready {
+    let is = |flag: libc::c_int| flags & flag != 0;
+
+    let mut ready = Ready::empty();
+    if is(libc::EPOLLIN) || is(libc::EPOLLPRI) {
+        ready |= Ready::readable();
+    }
+    if is(libc::EPOLLOUT) {
+        ready |= Ready::writable();
+    }
+    if is(libc::EPOLLHUP) || (is(libc::EPOLLIN) && is(libc::EPOLLRDHUP)) {
+        ready |= Ready::read_closed();
+    }
+    if is(libc::EPOLLHUP) || (is(libc::EPOLLOUT) && is(libc::EPOLLERR)) || flags == libc::EPOLLERR {
+        ready |= Ready::write_closed();
+    }
+    if is(libc::EPOLLERR) {
+        ready |= Ready::error();
+    }
+    if is(libc::EPOLLPRI) {
+        ready |= Ready::priority();
+    }
+    ready
+}
+
+fn syscall(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+impl Drop for Fd {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
@@ -0,0 +1,129 @@
+use std::{io, os::unix::io::RawFd, time::Duration};
+
+use crate::io::{Interest, Ready};
+
+/// IO resources that can be registered with the driver.
+pub trait Source: mio::event::Source {}
+
+impl<T: mio::event::Source + ?Sized> Source for T {}
+
+/// Source for a raw file descriptor owned by the caller.
+pub fn fd_source(fd: &RawFd) -> impl Source + '_ {
+    mio::unix::SourceFd(fd)
+}
+
+pub struct Selector {
+    poll: mio::Poll,
+}
+
+pub struct Registry {
+    registry: mio::Registry,
+}
+
+pub struct Waker {
+    waker: mio::Waker,
+}
+
+pub struct Events {
+    events: mio::Events,
+}
+
+/// Creates a selector and the registry used to add resources to it from any thread.
+pub fn selector() -> io::Result<(Selector, Registry)> {
+    let poll = mio::Poll::new()?;
+    let registry = poll.registry().try_clone()?;
+    Ok((Selector { poll }, Registry { registry }))
+}
+
+impl Selector {
+    pub fn select(&mut self, events: &mut Events, timeout: Option<Duration>) -> io::Result<()> {
+        self.poll.poll(&mut events.events, timeout)
+    }
+}
+
+impl Registry {
+    pub fn register(
+        &self,
+        source: &mut dyn Source,
+        token: usize,
+        interest: Interest,
+    ) -> io::Result<()> {
+        self.registry
+            .register(source, mio::Token(token), to_mio(interest))
+    }
+    pub fn reregister(
+        &self,
+        source: &mut dyn Source,
+        token: usize,
+        interest: Interest,
+    ) -> io::Result<()> {
+        self.registry
+            .reregister(source, mio::Token(token), to_mio(interest))
+    }
+    pub fn deregister(&self, source: &mut dyn Source) -> io::Result<()> {
+        self.registry.deregister(source)
+    }
+}
+
+impl Waker {
+    pub fn new(registry: &Registry, token: usize) -> io::Result<Self> {
+        let waker = mio::Waker::new(&registry.registry, mio::Token(token))?;
+        Ok(Self { waker })
+    }
+    pub fn wake(&self) -> io::Result<()> {
+        self.waker.wake()
+    }
+}
+
+impl Events {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: mio::Events::with_capacity(capacity),
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Ready)> + '_ {
+        self.events
+            .iter()
+            .map(|event| (event.token().0, ready(event)))
+    }
+}
+
+fn to_mio(interest: Interest) -> mio::Interest {
+    let mut flags: Option<mio::Interest> = None;
+    let mut add = |flag| flags = Some(flags.map_or(flag, |flags| flags | flag));
+    if interest.is_readable() {
+        add(mio::Interest::READABLE);
+    }
+    if interest.is_writable() {
+        add(mio::Interest::WRITABLE);
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if interest.is_priority() {
+        add(mio::Interest::PRIORITY);
+    }
+    // `Interest` can not be built empty.
+    flags.expect("empty interest")
+}
+
+fn ready(event: &mio::event::Event) -> Ready {
+    let mut ready = Ready::empty();
+    if event.is_readable() {
+        ready |= Ready::readable();
+    }
+    if event.is_writable() {
+        ready |= Ready::writable();
+    }
+    if event.is_read_closed() {
+        ready |= Ready::read_closed();
+    }
+    if event.is_write_closed() {
+        ready |= Ready::write_closed();
+    }
+    if event.is_error() {
+        ready |= Ready::error();
+    }
+    if event.is_priority() {
+        ready |= Ready::priority();
+    }
+    ready
+}
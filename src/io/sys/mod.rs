@@ -0,0 +1,14 @@
+//! OS readiness backends.
+//!
+//! Both expose the same `Selector`/`Registry`/`Waker`/`Events` surface to the driver: mio by
+//! default, or epoll directly on Linux with the `epoll` feature.
+
+#[cfg(not(feature = "epoll"))]
+mod mio;
+#[cfg(not(feature = "epoll"))]
+pub use self::mio::{fd_source, selector, Events, Registry, Selector, Source, Waker};
+
+#[cfg(feature = "epoll")]
+mod epoll;
+#[cfg(feature = "epoll")]
+pub use self::epoll::{fd_source, selector, Events, Registry, Selector, Source, Waker};
@@ -0,0 +1,14 @@
+pub mod blocking;
+pub mod dns;
+pub mod framed;
+pub mod io;
+pub mod park;
+pub mod runtime;
+pub mod scheduler;
+pub mod tcp;
+pub mod time;
+pub mod udp;
+pub mod unix;
+#[cfg(feature = "uring")]
+pub mod uring;
+pub mod util;
@@ -1,15 +1,8 @@
-mod framed;
-mod io;
-mod park;
-mod scheduler;
-mod tcp;
+use std::time::Duration;
 
-use std::thread;
-
-use crate::io::Driver;
-use crate::park::Parker;
-use crate::scheduler::Executor;
-use crate::tcp::TcpStream;
+use executor::runtime::Builder;
+use executor::tcp::TcpStream;
+use executor::time;
 
 use futures::{SinkExt, StreamExt};
 use rsc2_pb::{
@@ -21,11 +14,9 @@ use websocket_lite::ClientBuilder;
 fn main() -> Result<(), std::io::Error> {
     let addr = "127.0.0.1:5000".parse().unwrap();
 
-    // Setup the driver
-    let core = Driver::new();
-    let handle = core.handle();
-
-    let mut rt = Executor::new(Parker::new(core));
+    let mut rt = Builder::new_current_thread().enable_all().build()?;
+    let handle = rt.handle().io();
+    let timer_handle = rt.handle().timer();
 
     let stream = rt.block_on(TcpStream::connect(handle, addr))?;
     let client = ClientBuilder::new("ws://127.0.0.1:5000/sc2api").unwrap();
@@ -33,7 +24,7 @@ fn main() -> Result<(), std::io::Error> {
     rt.block_on(async {
         let mut framed = from_ws_client(client.async_connect_on(stream).await.unwrap());
         for req in 0..5 {
-            thread::sleep(std::time::Duration::from_secs(1));
+            time::sleep(&timer_handle, Duration::from_secs(1)).await;
 
             println!("SENDING...");
             let _ = framed.send(Request::new(req, RequestPing {})).await;
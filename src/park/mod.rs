@@ -1,19 +1,27 @@
+mod shared;
 mod thread;
 
-pub use thread::Parker;
+pub use shared::Parker;
+pub use thread::ParkThread;
 
 pub trait Park {
     type Handle: Unpark;
     fn handle(&self) -> Self::Handle;
     fn park(&mut self) -> Result<(), std::io::Error>;
     fn park_timeout(&mut self, dur: std::time::Duration) -> Result<(), std::io::Error>;
+
+    /// Parks when the scheduler has no runnable task left, a driver with a paused clock may
+    /// then move it to the next timer instead of blocking.
+    fn park_idle(&mut self) -> Result<(), std::io::Error> {
+        self.park()
+    }
 }
 
 pub trait Unpark: Sync + Send + 'static {
     fn unpark(&self);
 }
 
-impl<T: Unpark> Unpark for Box<T> {
+impl<T: Unpark + ?Sized> Unpark for Box<T> {
     fn unpark(&self) {
         (**self).unpark()
     }
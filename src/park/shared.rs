@@ -0,0 +1,226 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Condvar, Mutex,
+};
+use std::time::Duration;
+
+use crate::io;
+use crate::park::{Park, Unpark};
+
+#[derive(Debug)]
+struct ParkerState(AtomicUsize);
+
+impl ParkerState {
+    const EMPTY: usize = 0b0000;
+    const NOTIFIED: usize = 0b001;
+    const PARKED_COND: usize = 0b0010;
+    const PARKED_DRIV: usize = 0b0100;
+
+    fn new() -> Self {
+        Self(AtomicUsize::new(Self::EMPTY))
+    }
+
+    fn is_notified(&self) -> bool {
+        self.compare_exchange(
+            Self::NOTIFIED,
+            Self::EMPTY,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        )
+        .is_ok()
+    }
+
+    fn consume_notification(&self) -> Option<()> {
+        self.compare_exchange(
+            Self::NOTIFIED,
+            Self::EMPTY,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        )
+        .ok()
+        .map(|_| {})
+    }
+
+    fn update_from_empty(&self, s: usize) -> Option<()> {
+        self.compare_exchange(Self::EMPTY, s, Ordering::SeqCst, Ordering::SeqCst)
+            .map(|_| {})
+            .map_err(|value| {
+                if value == Self::NOTIFIED {
+                    let old = self.swap(Self::EMPTY, Ordering::SeqCst);
+                    debug_assert_eq!(old, Self::NOTIFIED, "park state changed unexpectedly");
+                    return;
+                }
+                panic!("inconsistent park_timeout state; actual = {}", value);
+            })
+            .ok()
+    }
+}
+
+impl std::ops::Deref for ParkerState {
+    type Target = AtomicUsize;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Parks the worker threads of a pool on one shared driver: whoever gets hold of it blocks in
+/// the driver, the others wait on their own condition variable.
+pub struct Parker<D: Park = io::Driver>(Arc<Inner<D>>);
+pub struct UnParker<D: Park = io::Driver>(Arc<Inner<D>>);
+
+struct Inner<D: Park> {
+    state: ParkerState,
+    m: Mutex<()>,
+    c: Condvar,
+    shared: Arc<Shared<D>>,
+}
+
+struct Shared<D: Park> {
+    handle: D::Handle,
+    driver: Mutex<D>,
+}
+
+impl<D: Park> Parker<D> {
+    pub fn new(driver: D) -> Self {
+        let handle = driver.handle();
+        Self(Arc::new(Inner {
+            state: ParkerState::new(),
+            m: Mutex::new(()),
+            c: Condvar::new(),
+            shared: Arc::new(Shared {
+                handle,
+                driver: Mutex::new(driver),
+            }),
+        }))
+    }
+}
+
+impl<D: Park> Clone for Parker<D> {
+    fn clone(&self) -> Self {
+        Self(Arc::new(Inner {
+            state: ParkerState::new(),
+            m: Mutex::new(()),
+            c: Condvar::new(),
+            shared: self.0.shared.clone(),
+        }))
+    }
+}
+
+impl<D> Park for Parker<D>
+where
+    D: Park + Send + 'static,
+{
+    type Handle = UnParker<D>;
+
+    fn handle(&self) -> Self::Handle {
+        UnParker(self.0.clone())
+    }
+    fn park(&mut self) -> Result<(), std::io::Error> {
+        self.0.park();
+        Ok(())
+    }
+    fn park_timeout(&mut self, dur: Duration) -> Result<(), std::io::Error> {
+        self.0.park_timeout(dur);
+        Ok(())
+    }
+    fn park_idle(&mut self) -> Result<(), std::io::Error> {
+        self.0.park_idle();
+        Ok(())
+    }
+}
+
+impl<D> Unpark for UnParker<D>
+where
+    D: Park + Send + 'static,
+{
+    fn unpark(&self) {
+        self.0.unpark()
+    }
+}
+
+impl<D: Park> Inner<D> {
+    fn park(&self) -> Option<()> {
+        self.park_with(false)
+    }
+
+    /// Parks on the driver as idle if we hold it, otherwise wakes up its holder so that it parks
+    /// again as idle.
+    fn park_idle(&self) -> Option<()> {
+        self.park_with(true)
+    }
+
+    fn park_with(&self, idle: bool) -> Option<()> {
+        for _ in 0..3 {
+            if self.state.consume_notification().is_some() {
+                return Some(());
+            }
+            std::thread::yield_now();
+        }
+
+        if let Ok(ref mut driver) = self.shared.driver.try_lock() {
+            self.state.update_from_empty(ParkerState::PARKED_DRIV)?;
+
+            if idle {
+                driver.park_idle().expect("couldn't park driver");
+            } else {
+                driver.park().expect("couldn't park driver");
+            }
+
+            match self.state.swap(ParkerState::EMPTY, Ordering::SeqCst) {
+                ParkerState::NOTIFIED | ParkerState::PARKED_DRIV => Some(()),
+                n => panic!("inconsistent park_timeout state: {}", n),
+            }
+        } else {
+            let lock = self.m.lock().unwrap();
+
+            self.state.update_from_empty(ParkerState::PARKED_COND)?;
+            if idle {
+                self.shared.handle.unpark();
+            }
+            drop(self.c.wait_while(lock, |_| !self.state.is_notified()));
+            Some(())
+        }
+    }
+
+    fn park_timeout(&self, dur: Duration) -> Option<()> {
+        if let Ok(ref mut driver) = self.shared.driver.try_lock() {
+            self.state.update_from_empty(ParkerState::PARKED_DRIV)?;
+
+            let parked = driver.park_timeout(dur).ok();
+
+            match self.state.swap(ParkerState::EMPTY, Ordering::SeqCst) {
+                ParkerState::NOTIFIED | ParkerState::PARKED_DRIV => parked,
+                n => panic!("inconsistent park_timeout state: {}", n),
+            }
+        } else {
+            let lock = self.m.lock().unwrap();
+
+            self.state.update_from_empty(ParkerState::PARKED_COND)?;
+            drop(
+                self.c
+                    .wait_timeout_while(lock, dur, |_| !self.state.is_notified()),
+            );
+
+            match self.state.swap(ParkerState::EMPTY, Ordering::SeqCst) {
+                ParkerState::EMPTY | ParkerState::NOTIFIED | ParkerState::PARKED_COND => Some(()),
+                n => panic!("inconsistent park_timeout state: {}", n),
+            }
+        }
+    }
+
+    fn unpark(&self) {
+        match self.state.swap(ParkerState::NOTIFIED, Ordering::SeqCst) {
+            ParkerState::EMPTY | ParkerState::NOTIFIED => {}
+
+            ParkerState::PARKED_COND => {
+                drop(self.m.lock().unwrap());
+                self.c.notify_one()
+            }
+
+            ParkerState::PARKED_DRIV => {
+                self.shared.handle.unpark();
+            }
+            actual => panic!("inconsistent state in unpark; actual = {}", actual),
+        }
+    }
+}
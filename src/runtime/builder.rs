@@ -0,0 +1,205 @@
+use std::io;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::driver::Driver;
+use super::{Handle, Kind, Runtime, Spawner, ThreadConfig};
+use crate::blocking::{BlockingPool, DEFAULT_KEEP_ALIVE, DEFAULT_MAX_THREADS};
+use crate::park::Parker;
+use crate::scheduler::{Executor, ThreadPool};
+
+/// Configures and builds a `Runtime`, IO and timers are enabled by default.
+pub struct Builder {
+    flavor: Flavor,
+    worker_threads: Option<usize>,
+    max_blocking_threads: usize,
+    keep_alive: Duration,
+    event_capacity: usize,
+    enable_io: bool,
+    enable_time: bool,
+    thread_name: String,
+    thread_stack_size: Option<usize>,
+    on_thread_start: Option<Arc<dyn Fn() + Send + Sync>>,
+    on_thread_stop: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+#[derive(Clone, Copy)]
+enum Flavor {
+    CurrentThread,
+    MultiThread,
+}
+
+impl Builder {
+    /// Tasks run on the thread calling `block_on`.
+    pub fn new_current_thread() -> Self {
+        Self::new(Flavor::CurrentThread)
+    }
+
+    /// Tasks run on a pool of workers, one per CPU unless `worker_threads` is set.
+    pub fn new_multi_thread() -> Self {
+        Self::new(Flavor::MultiThread)
+    }
+
+    fn new(flavor: Flavor) -> Self {
+        Self {
+            flavor,
+            worker_threads: None,
+            max_blocking_threads: DEFAULT_MAX_THREADS,
+            keep_alive: DEFAULT_KEEP_ALIVE,
+            event_capacity: 1024,
+            enable_io: true,
+            enable_time: true,
+            thread_name: "executor".to_string(),
+            thread_stack_size: None,
+            on_thread_start: None,
+            on_thread_stop: None,
+        }
+    }
+
+    /// Number of workers of a multi-thread runtime, ignored by a current-thread one.
+    pub fn worker_threads(&mut self, n: usize) -> &mut Self {
+        assert!(n > 0, "worker_threads must be at least 1");
+        self.worker_threads = Some(n);
+        self
+    }
+    pub fn max_blocking_threads(&mut self, n: usize) -> &mut Self {
+        assert!(n > 0, "max_blocking_threads must be at least 1");
+        self.max_blocking_threads = n;
+        self
+    }
+    /// How long an idle thread of the blocking pool waits for work before exiting.
+    pub fn thread_keep_alive(&mut self, duration: Duration) -> &mut Self {
+        self.keep_alive = duration;
+        self
+    }
+    /// Number of events handled per turn of the IO driver.
+    pub fn event_capacity(&mut self, capacity: usize) -> &mut Self {
+        assert!(capacity > 0, "event_capacity must be at least 1");
+        self.event_capacity = capacity;
+        self
+    }
+    pub fn enable_io(&mut self, enable: bool) -> &mut Self {
+        self.enable_io = enable;
+        self
+    }
+    pub fn enable_time(&mut self, enable: bool) -> &mut Self {
+        self.enable_time = enable;
+        self
+    }
+    pub fn enable_all(&mut self) -> &mut Self {
+        self.enable_io(true).enable_time(true)
+    }
+
+    /// Prefix of the thread names, workers are named `{name}-worker-{index}` and blocking
+    /// threads `{name}-blocking`.
+    pub fn thread_name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.thread_name = name.into();
+        self
+    }
+    pub fn thread_stack_size(&mut self, size: usize) -> &mut Self {
+        self.thread_stack_size = Some(size);
+        self
+    }
+
+    /// Called on every thread started by the runtime before it runs anything, the thread
+    /// calling `block_on` is not one of them.
+    pub fn on_thread_start<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_thread_start = Some(Arc::new(f));
+        self
+    }
+    /// Called on every thread started by the runtime right before it exits.
+    pub fn on_thread_stop<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_thread_stop = Some(Arc::new(f));
+        self
+    }
+
+    pub fn build(&mut self) -> io::Result<Runtime> {
+        let io = if self.enable_io {
+            Some(self.event_capacity)
+        } else {
+            None
+        };
+        let (driver, resources) = Driver::new(io, self.enable_time);
+
+        let threads = self.thread_config();
+        let blocking = BlockingPool::with_threads(
+            threads.with_name(format!("{}-blocking", self.thread_name)),
+            self.max_blocking_threads,
+            self.keep_alive,
+        );
+
+        let (kind, spawner) = match self.flavor {
+            Flavor::CurrentThread => {
+                let executor = Executor::with_blocking_pool(driver, blocking);
+                let spawner = Spawner::CurrentThread(executor.spawner());
+                (Kind::CurrentThread(executor), spawner)
+            }
+            Flavor::MultiThread => {
+                let size = match self.worker_threads {
+                    Some(size) => size,
+                    None => thread::available_parallelism()?.get(),
+                };
+                let pool = ThreadPool::with_threads(size, Parker::new(driver), blocking, threads);
+                let spawner = Spawner::MultiThread(pool.spawner());
+                (Kind::MultiThread(pool), spawner)
+            }
+        };
+
+        Ok(Runtime {
+            kind,
+            handle: Handle {
+                spawner,
+                io: resources.io,
+                timer: resources.timer,
+            },
+        })
+    }
+
+    fn thread_config(&self) -> ThreadConfig {
+        let mut config = ThreadConfig::new(self.thread_name.clone());
+        if let Some(size) = self.thread_stack_size {
+            config = config.stack_size(size);
+        }
+        if let Some(f) = &self.on_thread_start {
+            config = config.on_start(f.clone());
+        }
+        if let Some(f) = &self.on_thread_stop {
+            config = config.on_stop(f.clone());
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Builder;
+    use crate::time;
+
+    #[test]
+    fn builds_every_flavor() {
+        for mut builder in [Builder::new_current_thread(), Builder::new_multi_thread()] {
+            let mut rt = builder.worker_threads(2).enable_all().build().unwrap();
+            let timer = rt.handle().timer();
+            let handle = rt.spawn(async move {
+                time::sleep(&timer, Duration::from_millis(10)).await;
+                1
+            });
+            assert_eq!(rt.block_on(handle).unwrap(), 1);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "event_capacity must be at least 1")]
+    fn event_capacity_is_not_zero() {
+        Builder::new_current_thread().event_capacity(0);
+    }
+}
@@ -0,0 +1,106 @@
+use std::io;
+use std::time::Duration;
+
+use crate::io as net;
+use crate::park::{Park, ParkThread, Unpark};
+use crate::time;
+
+/// Driver stack of a runtime, depends on whether IO and timers are enabled.
+pub(super) enum Driver {
+    Thread(ParkThread),
+    Io(net::Driver),
+    Time(time::Driver<ParkThread>),
+    IoTime(time::Driver<net::Driver>),
+}
+
+/// Handles to the resources of the drivers that are enabled.
+pub(super) struct Resources {
+    pub(super) io: Option<net::Handle>,
+    pub(super) timer: Option<time::Handle>,
+}
+
+impl Driver {
+    pub(super) fn new(io: Option<usize>, time: bool) -> (Self, Resources) {
+        match (io, time) {
+            (None, false) => (
+                Driver::Thread(ParkThread::new()),
+                Resources {
+                    io: None,
+                    timer: None,
+                },
+            ),
+            (Some(capacity), false) => {
+                let core = net::Driver::with_capacity(capacity);
+                let io = core.handle();
+                (
+                    Driver::Io(core),
+                    Resources {
+                        io: Some(io),
+                        timer: None,
+                    },
+                )
+            }
+            (None, true) => {
+                let timer = time::Driver::new(ParkThread::new());
+                let handle = timer.handle();
+                (
+                    Driver::Time(timer),
+                    Resources {
+                        io: None,
+                        timer: Some(handle),
+                    },
+                )
+            }
+            (Some(capacity), true) => {
+                let core = net::Driver::with_capacity(capacity);
+                let io = core.handle();
+                let timer = time::Driver::new(core);
+                let handle = timer.handle();
+                (
+                    Driver::IoTime(timer),
+                    Resources {
+                        io: Some(io),
+                        timer: Some(handle),
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl Park for Driver {
+    type Handle = Box<dyn Unpark>;
+
+    fn handle(&self) -> Self::Handle {
+        match self {
+            Driver::Thread(park) => Box::new(park.handle()),
+            Driver::Io(park) => Box::new(Park::handle(park)),
+            Driver::Time(park) => Box::new(Park::handle(park)),
+            Driver::IoTime(park) => Box::new(Park::handle(park)),
+        }
+    }
+    fn park(&mut self) -> Result<(), io::Error> {
+        match self {
+            Driver::Thread(park) => park.park(),
+            Driver::Io(park) => park.park(),
+            Driver::Time(park) => park.park(),
+            Driver::IoTime(park) => park.park(),
+        }
+    }
+    fn park_timeout(&mut self, dur: Duration) -> Result<(), io::Error> {
+        match self {
+            Driver::Thread(park) => park.park_timeout(dur),
+            Driver::Io(park) => park.park_timeout(dur),
+            Driver::Time(park) => park.park_timeout(dur),
+            Driver::IoTime(park) => park.park_timeout(dur),
+        }
+    }
+    fn park_idle(&mut self) -> Result<(), io::Error> {
+        match self {
+            Driver::Thread(park) => park.park_idle(),
+            Driver::Io(park) => park.park_idle(),
+            Driver::Time(park) => park.park_idle(),
+            Driver::IoTime(park) => park.park_idle(),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+mod builder;
+mod driver;
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::io;
+use crate::scheduler::{self, thread_pool, Executor, JoinHandle, ThreadPool};
+use crate::time;
+
+pub use crate::util::ThreadConfig;
+pub use builder::Builder;
+
+/// Scheduler and drivers configured by a `Builder`.
+pub struct Runtime {
+    kind: Kind,
+    handle: Handle,
+}
+
+enum Kind {
+    CurrentThread(Executor<driver::Driver>),
+    MultiThread(ThreadPool),
+}
+
+/// Cloneable handle to a `Runtime`, spawns tasks and gives access to the enabled drivers.
+#[derive(Clone)]
+pub struct Handle {
+    spawner: Spawner,
+    io: Option<io::Handle>,
+    timer: Option<time::Handle>,
+}
+
+#[derive(Clone)]
+enum Spawner {
+    CurrentThread(scheduler::Spawner),
+    MultiThread(thread_pool::Spawner),
+}
+
+impl Runtime {
+    /// Runs `f` to completion, a current-thread runtime only makes progress on its tasks
+    /// while this is called.
+    pub fn block_on<F: Future>(&mut self, f: F) -> F::Output {
+        match &mut self.kind {
+            Kind::CurrentThread(executor) => executor.block_on(f),
+            Kind::MultiThread(pool) => pool.block_on(f),
+        }
+    }
+
+    pub fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.handle.spawn(f)
+    }
+
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.handle.spawn_blocking(f)
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Drops the runtime, waiting up to `timeout` for the blocking closures to finish.
+    pub fn shutdown_timeout(self, timeout: Duration) {
+        match self.kind {
+            Kind::CurrentThread(executor) => executor.shutdown_timeout(timeout),
+            Kind::MultiThread(pool) => pool.shutdown_timeout(timeout),
+        }
+    }
+}
+
+impl Handle {
+    pub fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        match &self.spawner {
+            Spawner::CurrentThread(spawner) => spawner.spawn(f),
+            Spawner::MultiThread(spawner) => spawner.spawn(f),
+        }
+    }
+
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        match &self.spawner {
+            Spawner::CurrentThread(spawner) => spawner.spawn_blocking(f),
+            Spawner::MultiThread(spawner) => spawner.spawn_blocking(f),
+        }
+    }
+
+    /// Handle of the IO driver, panics if IO is disabled.
+    pub fn io(&self) -> io::Handle {
+        self.try_io()
+            .expect("IO is disabled on this runtime, call `Builder::enable_io`")
+    }
+    /// Handle of the IO driver, `None` if IO is disabled.
+    pub fn try_io(&self) -> Option<io::Handle> {
+        self.io.clone()
+    }
+
+    /// Handle of the timer, panics if timers are disabled.
+    pub fn timer(&self) -> time::Handle {
+        self.try_timer()
+            .expect("timers are disabled on this runtime, call `Builder::enable_time`")
+    }
+    /// Handle of the timer, `None` if timers are disabled.
+    pub fn try_timer(&self) -> Option<time::Handle> {
+        self.timer.clone()
+    }
+}
@@ -0,0 +1,222 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::mem::{self, ManuallyDrop};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use slab::Slab;
+
+use super::task::harness;
+use super::{JoinHandle, UnparkState, MAX_TASKS_PER_TICK, VTABLE};
+use crate::park::{Park, Unpark};
+
+const TASK_VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |clone: *const ()| unsafe {
+        Arc::increment_strong_count(clone as *const TaskRef);
+        RawWaker::new(clone, &TASK_VTABLE)
+    },
+    |wake: *const ()| unsafe {
+        Arc::from_raw(wake as *const TaskRef).wake();
+    },
+    |wake_by_ref: *const ()| unsafe {
+        let arc = ManuallyDrop::new(Arc::from_raw(wake_by_ref as *const TaskRef));
+        arc.wake();
+    },
+    |drop_waker: *const ()| unsafe {
+        drop(Arc::from_raw(drop_waker as *const TaskRef));
+    },
+);
+
+/// Current-thread executor for futures that are not `Send`.
+///
+/// The tasks never leave the thread, only their wakers do: waking a task pushes its key on a
+/// shared queue that is drained by `block_on`.
+pub struct LocalExecutor<P> {
+    park: P,
+    state: Arc<UnparkState>,
+    tasks: Rc<Tasks>,
+}
+
+/// Cloneable handle used to spawn `!Send` tasks on a `LocalExecutor` from the same thread.
+#[derive(Clone)]
+pub struct LocalSpawner {
+    tasks: Rc<Tasks>,
+}
+
+struct Tasks {
+    slab: RefCell<Slab<Option<LocalTask>>>,
+    woken: Arc<Woken>,
+    closed: Cell<bool>,
+}
+
+struct LocalTask {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    waker: Arc<TaskRef>,
+}
+
+/// Thread-safe part of a task, referenced by its wakers.
+struct TaskRef {
+    key: usize,
+    scheduled: AtomicBool,
+    woken: Arc<Woken>,
+}
+
+struct Woken {
+    queue: Mutex<VecDeque<usize>>,
+    unpark: Box<dyn Unpark>,
+}
+
+impl<P: Park> LocalExecutor<P> {
+    pub fn new(park: P) -> Self {
+        let unpark = park.handle();
+        let woken = Arc::new(Woken {
+            queue: Mutex::new(VecDeque::new()),
+            unpark: Box::new(park.handle()),
+        });
+        Self {
+            park,
+            state: Arc::new(UnparkState::new(Box::new(unpark))),
+            tasks: Rc::new(Tasks {
+                slab: RefCell::new(Slab::new()),
+                woken,
+                closed: Cell::new(false),
+            }),
+        }
+    }
+
+    pub fn spawner(&self) -> LocalSpawner {
+        LocalSpawner {
+            tasks: self.tasks.clone(),
+        }
+    }
+
+    pub fn spawn_local<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        self.tasks.spawn(f)
+    }
+
+    pub fn block_on<F: Future>(&mut self, mut f: F) -> F::Output {
+        let mut f = unsafe { Pin::new_unchecked(&mut f) };
+
+        let raw_waker = RawWaker::new(&*self.state as *const UnparkState as *const (), &VTABLE);
+        let waker = ManuallyDrop::new(unsafe { Waker::from_raw(raw_waker) });
+
+        let mut cx = Context::from_waker(&waker);
+        self.state.notified.store(true, Ordering::SeqCst);
+        loop {
+            if self.state.take_notification() {
+                if let Poll::Ready(o) = f.as_mut().poll(&mut cx) {
+                    return o;
+                }
+            }
+            // tick the scheduler
+            let busy = self.tick();
+
+            if busy || self.state.is_notified() {
+                self.park
+                    .park_timeout(std::time::Duration::from_millis(0))
+                    .expect("problem parking");
+            } else {
+                self.park.park_idle().expect("problem parking");
+            }
+        }
+    }
+
+    /// Runs the tasks that were woken, returns true if some are still waiting to run.
+    fn tick(&mut self) -> bool {
+        for _ in 0..MAX_TASKS_PER_TICK {
+            let key = match self.tasks.woken.queue.lock().unwrap().pop_front() {
+                Some(key) => key,
+                None => return false,
+            };
+            self.tasks.run(key);
+        }
+        !self.tasks.woken.queue.lock().unwrap().is_empty()
+    }
+}
+
+impl<P> Drop for LocalExecutor<P> {
+    fn drop(&mut self) {
+        self.tasks.closed.set(true);
+        let tasks = mem::take(&mut *self.tasks.slab.borrow_mut());
+        drop(tasks);
+        drop(mem::take(&mut *self.tasks.woken.queue.lock().unwrap()));
+    }
+}
+
+impl LocalSpawner {
+    pub fn spawn_local<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        self.tasks.spawn(f)
+    }
+}
+
+impl Tasks {
+    fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let (harness, handle) = harness(f);
+        if self.closed.get() {
+            return handle;
+        }
+
+        let mut slab = self.slab.borrow_mut();
+        let entry = slab.vacant_entry();
+        let waker = Arc::new(TaskRef {
+            key: entry.key(),
+            scheduled: AtomicBool::new(false),
+            woken: self.woken.clone(),
+        });
+        entry.insert(Some(LocalTask {
+            future: Box::pin(harness),
+            waker: waker.clone(),
+        }));
+        drop(slab);
+
+        waker.wake();
+        handle
+    }
+
+    fn run(&self, key: usize) {
+        // The task is taken out of the slab while it is polled so that it can spawn.
+        let mut task = match self.slab.borrow_mut().get_mut(key).and_then(Option::take) {
+            Some(task) => task,
+            None => return,
+        };
+        task.waker.scheduled.store(false, Ordering::Release);
+
+        let raw_waker = RawWaker::new(&*task.waker as *const TaskRef as *const (), &TASK_VTABLE);
+        let waker = ManuallyDrop::new(unsafe { Waker::from_raw(raw_waker) });
+        let mut cx = Context::from_waker(&waker);
+
+        if task.future.as_mut().poll(&mut cx).is_ready() {
+            self.slab.borrow_mut().remove(key);
+            drop(task);
+        } else if let Some(slot) = self.slab.borrow_mut().get_mut(key) {
+            *slot = Some(task);
+        }
+    }
+}
+
+impl TaskRef {
+    fn wake(&self) {
+        if !self.scheduled.swap(true, Ordering::AcqRel) {
+            self.woken.queue.lock().unwrap().push_back(self.key);
+            self.woken.unpark.unpark();
+        }
+    }
+}
@@ -0,0 +1,648 @@
+mod local;
+mod task;
+pub mod thread_pool;
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::mem::{self, ManuallyDrop};
+use std::pin::Pin;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::time::Duration;
+
+use crate::blocking::{
+    BlockingPool, DEFAULT_KEEP_ALIVE, DEFAULT_MAX_THREADS, DEFAULT_SHUTDOWN_TIMEOUT,
+};
+use crate::park::{Park, Unpark};
+
+pub use local::{LocalExecutor, LocalSpawner};
+pub(crate) use task::blocking;
+pub use task::{JoinError, JoinHandle};
+use task::{OwnedTasks, Schedule, Task};
+pub use thread_pool::{block_in_place, ThreadPool};
+
+/// Maximum number of spawned tasks polled between two polls of the root future.
+const MAX_TASKS_PER_TICK: usize = 61;
+
+const VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |clone: *const ()| unsafe {
+        Arc::increment_strong_count(clone as *const UnparkState);
+        RawWaker::new(clone, &VTABLE)
+    },
+    |wake: *const ()| unsafe {
+        let up = &Arc::from_raw(wake as *const UnparkState);
+        up.wake();
+    },
+    |wake_by_ref: *const ()| unsafe {
+        let up = ManuallyDrop::new(Arc::from_raw(wake_by_ref as *const UnparkState));
+        up.wake();
+    },
+    |drop_waker: *const ()| unsafe {
+        drop(Arc::from_raw(drop_waker as *const UnparkState));
+    },
+);
+
+pub struct Executor<P> {
+    park: P,
+    state: Arc<UnparkState>,
+    shared: Arc<Shared>,
+    shutdown_timeout: Duration,
+}
+
+/// Cloneable handle used to spawn tasks on an `Executor`, possibly from another thread.
+#[derive(Clone)]
+pub struct Spawner {
+    shared: Arc<Shared>,
+}
+
+struct UnparkState {
+    unpark: Box<dyn Unpark>,
+    /// Set when the root future has been woken and must be polled again.
+    notified: AtomicBool,
+}
+
+struct Shared {
+    queue: Mutex<VecDeque<Arc<Task>>>,
+    owned: OwnedTasks,
+    unpark: Box<dyn Unpark>,
+    blocking: BlockingPool,
+}
+
+impl<P: Park> Executor<P> {
+    pub fn new(park: P) -> Self {
+        let blocking =
+            BlockingPool::new("executor-blocking", DEFAULT_MAX_THREADS, DEFAULT_KEEP_ALIVE);
+        Self::with_blocking_pool(park, blocking)
+    }
+
+    /// Uses `blocking` to run the closures given to `spawn_blocking`, it is shut down when
+    /// the executor is dropped.
+    pub fn with_blocking_pool(park: P, blocking: BlockingPool) -> Self {
+        let unpark = park.handle();
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(VecDeque::new()),
+            owned: OwnedTasks::new(),
+            unpark: Box::new(park.handle()),
+            blocking,
+        });
+        Self {
+            park,
+            state: Arc::new(UnparkState::new(Box::new(unpark))),
+            shared,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        }
+    }
+
+    /// Drops the executor, waiting up to `timeout` instead of `DEFAULT_SHUTDOWN_TIMEOUT` for
+    /// the blocking closures to finish.
+    pub fn shutdown_timeout(mut self, timeout: Duration) {
+        self.shutdown_timeout = timeout;
+    }
+
+    pub fn spawner(&self) -> Spawner {
+        Spawner {
+            shared: self.shared.clone(),
+        }
+    }
+
+    pub fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.shared.spawn(&self.shared, f)
+    }
+
+    /// Runs `f` on the blocking pool, the executor's thread is free to run other tasks.
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.shared.blocking.spawn(f)
+    }
+
+    pub fn block_on<F: Future>(&mut self, mut f: F) -> F::Output {
+        let mut f = unsafe { Pin::new_unchecked(&mut f) };
+
+        let raw_waker = RawWaker::new(&*self.state as *const UnparkState as *const (), &VTABLE);
+        let waker = ManuallyDrop::new(unsafe { Waker::from_raw(raw_waker) });
+
+        let mut cx = Context::from_waker(&waker);
+        self.state.notified.store(true, Ordering::SeqCst);
+        loop {
+            if self.state.take_notification() {
+                if let Poll::Ready(o) = f.as_mut().poll(&mut cx) {
+                    return o;
+                }
+            }
+            // tick the scheduler
+            let busy = self.tick();
+
+            if busy || self.state.is_notified() {
+                // Only poll the driver, there is more work to do right away.
+                self.park
+                    .park_timeout(std::time::Duration::from_millis(0))
+                    .expect("problem parking");
+            } else {
+                self.park.park_idle().expect("problem parking");
+            }
+        }
+    }
+
+    /// Runs the tasks that were woken, returns true if some are still waiting to run.
+    fn tick(&mut self) -> bool {
+        for _ in 0..MAX_TASKS_PER_TICK {
+            let task = match self.shared.queue.lock().unwrap().pop_front() {
+                Some(task) => task,
+                None => return false,
+            };
+            if task.run() {
+                self.shared.owned.release(&task);
+            }
+        }
+        !self.shared.queue.lock().unwrap().is_empty()
+    }
+}
+
+impl UnparkState {
+    fn new(unpark: Box<dyn Unpark>) -> Self {
+        Self {
+            unpark,
+            notified: AtomicBool::new(true),
+        }
+    }
+
+    fn wake(&self) {
+        self.notified.store(true, Ordering::SeqCst);
+        self.unpark.unpark();
+    }
+
+    fn is_notified(&self) -> bool {
+        self.notified.load(Ordering::SeqCst)
+    }
+
+    fn take_notification(&self) -> bool {
+        self.notified.swap(false, Ordering::SeqCst)
+    }
+}
+
+impl<P> Drop for Executor<P> {
+    fn drop(&mut self) {
+        self.shared.owned.close();
+        let queue = mem::take(&mut *self.shared.queue.lock().unwrap());
+        drop(queue);
+        // Let the blocking closures finish, they may still use the drivers.
+        self.shared.blocking.shutdown(Some(self.shutdown_timeout));
+    }
+}
+
+impl Spawner {
+    pub fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.shared.spawn(&self.shared, f)
+    }
+
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.shared.blocking.spawn(f)
+    }
+}
+
+impl Shared {
+    fn spawn<F>(&self, me: &Arc<Shared>, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, handle) = self.owned.bind(f, me.clone());
+        if let Some(task) = task {
+            self.schedule(task);
+        }
+        handle
+    }
+}
+
+impl Schedule for Shared {
+    fn schedule(&self, task: Arc<Task>) {
+        self.queue.lock().unwrap().push_back(task);
+        self.unpark.unpark();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::thread::{self, ThreadId};
+    use std::time::{Duration, Instant};
+
+    use futures::channel::oneshot;
+    use futures::future;
+    use futures::FutureExt;
+
+    use super::{block_in_place, Executor, LocalExecutor, ThreadPool};
+    use crate::io;
+    use crate::park::{Park, Parker};
+    use crate::time;
+
+    /// Returns `Pending` once after waking itself, the task is polled again from a run queue.
+    async fn yield_now() {
+        let mut yielded = false;
+        future::poll_fn(|cx| {
+            if yielded {
+                return Poll::Ready(());
+            }
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await
+    }
+
+    #[test]
+    fn stale_waker_does_not_release_a_reused_slot() {
+        let mut executor = Executor::new(io::Driver::new());
+        let stored = Arc::new(Mutex::new(None::<Waker>));
+
+        let waker = stored.clone();
+        let first = executor.spawn(future::poll_fn(move |cx: &mut Context<'_>| {
+            *waker.lock().unwrap() = Some(cx.waker().clone());
+            Poll::Ready(())
+        }));
+        executor.block_on(first).unwrap();
+
+        // Takes the key of the completed task, only the executor keeps it alive.
+        let second = executor.spawn(future::pending::<()>());
+        stored.lock().unwrap().take().unwrap().wake();
+        executor.block_on(yield_now());
+
+        assert!(second.now_or_never().is_none());
+    }
+
+    /// Spins until `done` returns true, panics after a few seconds.
+    fn wait_until(done: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::yield_now();
+        }
+    }
+
+    fn thread_pool(size: usize) -> ThreadPool {
+        ThreadPool::new(size, Parker::new(io::Driver::new()))
+    }
+
+    #[test]
+    fn pool_runs_tasks_on_several_workers() {
+        let pool = thread_pool(4);
+        let running = Arc::new(AtomicUsize::new(0));
+
+        // Each task only returns once the other one runs at the same time.
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let running = running.clone();
+                pool.spawn(async move {
+                    running.fetch_add(1, Ordering::SeqCst);
+                    wait_until(|| running.load(Ordering::SeqCst) == 2);
+                    thread::current().id()
+                })
+            })
+            .collect();
+
+        let ids: Vec<ThreadId> = pool.block_on(future::try_join_all(handles)).unwrap();
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn idle_workers_are_woken_by_spawn() {
+        let pool = thread_pool(2);
+        // Let every worker run out of work and park.
+        thread::sleep(Duration::from_millis(50));
+
+        let spawner = pool.spawner();
+        let handle = thread::spawn(move || spawner.spawn(async { 3 }))
+            .join()
+            .unwrap();
+        let (tx, rx) = mpsc::channel();
+        pool.spawn(async move { tx.send(handle.await.unwrap()).unwrap() });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(3));
+    }
+
+    #[test]
+    fn tasks_queued_on_a_busy_worker_are_stolen() {
+        let pool = thread_pool(2);
+        let spawner = pool.spawner();
+        let ran = Arc::new(AtomicUsize::new(0));
+
+        let counter = ran.clone();
+        let busy = pool.spawn(async move {
+            // Queued on this worker, which is kept busy until the other one ran them all.
+            let worker = thread::current().id();
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    let counter = counter.clone();
+                    spawner.spawn(async move {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        thread::current().id()
+                    })
+                })
+                .collect();
+            wait_until(|| counter.load(Ordering::SeqCst) == 8);
+            (worker, handles)
+        });
+
+        let (worker, handles) = pool.block_on(busy).unwrap();
+        let ids = pool.block_on(future::try_join_all(handles)).unwrap();
+        assert!(ids.iter().all(|&id| id != worker));
+        assert_eq!(ran.load(Ordering::SeqCst), 8);
+    }
+
+    #[test]
+    fn join_handle_completes_from_another_worker() {
+        let pool = thread_pool(2);
+        let spawner = pool.spawner();
+        let started = Arc::new(AtomicBool::new(false));
+        let waiting = Arc::new(AtomicBool::new(false));
+
+        let (started2, waiting2) = (started.clone(), waiting.clone());
+        let outer = pool.spawn(async move {
+            let outer = thread::current().id();
+            let mut inner = spawner.spawn(async move {
+                started2.store(true, Ordering::SeqCst);
+                // Completes once the outer task waits on the handle.
+                wait_until(|| waiting2.load(Ordering::SeqCst));
+                thread::current().id()
+            });
+            // Busy until the inner task is stolen by the other worker.
+            wait_until(|| started.load(Ordering::SeqCst));
+
+            let inner = future::poll_fn(|cx| {
+                let poll = Pin::new(&mut inner).poll(cx);
+                waiting.store(true, Ordering::SeqCst);
+                poll
+            })
+            .await;
+            (outer, inner.unwrap())
+        });
+
+        let (outer, inner) = pool.block_on(outer).unwrap();
+        assert_ne!(outer, inner);
+    }
+
+    #[test]
+    fn spawn_local_runs_futures_that_are_not_send() {
+        let mut executor = LocalExecutor::new(io::Driver::new());
+        let counter = Rc::new(Cell::new(0));
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                executor.spawn_local(async move {
+                    yield_now().await;
+                    counter.set(counter.get() + 1);
+                })
+            })
+            .collect();
+        executor.block_on(async {
+            for handle in handles {
+                handle.await.unwrap();
+            }
+        });
+        assert_eq!(counter.get(), 4);
+    }
+
+    #[test]
+    fn local_task_woken_from_another_thread() {
+        let mut executor = LocalExecutor::new(io::Driver::new());
+        let (tx, rx) = oneshot::channel();
+        let local = Rc::new(());
+
+        let handle = executor.spawn_local(async move {
+            let value = rx.await.unwrap();
+            drop(local);
+            value
+        });
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            tx.send(7).unwrap();
+        });
+        assert_eq!(executor.block_on(handle).unwrap(), 7);
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn local_task_spawns_while_polled() {
+        let mut executor = LocalExecutor::new(io::Driver::new());
+        let spawner = executor.spawner();
+
+        let handle = executor.spawn_local(async move {
+            let inner = spawner.spawn_local(async { Rc::new(5) });
+            *inner.await.unwrap()
+        });
+        assert_eq!(executor.block_on(handle).unwrap(), 5);
+    }
+
+    #[test]
+    fn local_join_handles_are_cancelled_on_drop() {
+        let executor = LocalExecutor::new(io::Driver::new());
+        let spawner = executor.spawner();
+        let pending = executor.spawn_local(future::pending::<()>());
+        drop(executor);
+
+        let late = spawner.spawn_local(async {});
+        let mut other = Executor::new(io::Driver::new());
+        assert!(other.block_on(pending).unwrap_err().is_cancelled());
+        assert!(other.block_on(late).unwrap_err().is_cancelled());
+    }
+
+    /// Counts how the executor parks, the actual parking is left to an IO driver.
+    struct CountingPark {
+        inner: io::Driver,
+        parks: Arc<AtomicUsize>,
+        polls: Arc<AtomicUsize>,
+    }
+
+    impl Park for CountingPark {
+        type Handle = <io::Driver as Park>::Handle;
+
+        fn handle(&self) -> Self::Handle {
+            self.inner.handle()
+        }
+        fn park(&mut self) -> Result<(), std::io::Error> {
+            self.parks.fetch_add(1, Ordering::SeqCst);
+            self.inner.park()
+        }
+        fn park_timeout(&mut self, dur: Duration) -> Result<(), std::io::Error> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            self.inner.park_timeout(dur)
+        }
+    }
+
+    #[test]
+    fn block_on_parks_once_while_pending() {
+        let parks = Arc::new(AtomicUsize::new(0));
+        let polls = Arc::new(AtomicUsize::new(0));
+        let mut executor = Executor::new(CountingPark {
+            inner: io::Driver::new(),
+            parks: parks.clone(),
+            polls: polls.clone(),
+        });
+
+        let (tx, rx) = oneshot::channel();
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            tx.send(()).unwrap();
+        });
+        executor.block_on(rx).unwrap();
+        sender.join().unwrap();
+
+        // Parked until the sender woke the root future, without spinning on the driver.
+        assert_eq!(parks.load(Ordering::SeqCst), 1);
+        assert_eq!(polls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn drop_bounds_the_wait_for_blocking_closures() {
+        let executor = Executor::new(io::Driver::new());
+        let (tx, rx) = mpsc::channel::<()>();
+        executor.spawn_blocking(move || rx.recv());
+
+        let start = Instant::now();
+        executor.shutdown_timeout(Duration::from_millis(100));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        drop(tx);
+    }
+
+    #[test]
+    fn drop_from_a_blocking_closure() {
+        let executor = Executor::new(io::Driver::new());
+        let (send_executor, recv_executor) = mpsc::channel::<Executor<io::Driver>>();
+        let (done, dropped) = mpsc::channel();
+        executor.spawn_blocking(move || {
+            drop(recv_executor.recv().unwrap());
+            done.send(()).unwrap();
+        });
+
+        send_executor.send(executor).unwrap();
+        // Waiting for itself would only stop at the shutdown timeout.
+        dropped.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+
+    #[test]
+    fn task_drops_its_own_pool() {
+        let pool = thread_pool(2);
+        let (send_pool, recv_pool) = oneshot::channel::<ThreadPool>();
+        let (done, dropped) = mpsc::channel();
+        let other = pool.spawn(future::pending::<()>());
+
+        pool.spawn(async move {
+            drop(recv_pool.await.unwrap());
+            done.send(()).unwrap();
+        });
+        assert!(send_pool.send(pool).is_ok());
+        dropped.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(futures::executor::block_on(other)
+            .unwrap_err()
+            .is_cancelled());
+    }
+
+    #[test]
+    fn block_in_place_hands_the_worker_over() {
+        let pool = thread_pool(1);
+        let spawner = pool.spawner();
+
+        let blocking = pool.spawn(async move {
+            let (tx, rx) = mpsc::channel();
+            // Queued on the worker that is about to block.
+            let queued = spawner.spawn(async move {
+                let current = thread::current();
+                tx.send((current.id(), current.name().map(str::to_owned)))
+                    .unwrap();
+            });
+            let received = block_in_place(|| rx.recv_timeout(Duration::from_secs(5)));
+            (thread::current().id(), received, queued)
+        });
+
+        let (blocked, received, queued) = pool.block_on(blocking).unwrap();
+        let (id, name) = received.unwrap();
+        assert_ne!(id, blocked);
+        assert_eq!(name.as_deref(), Some("executor-worker-0"));
+        pool.block_on(queued).unwrap();
+    }
+
+    #[test]
+    fn block_in_place_keeps_the_driver_turning() {
+        let timer = time::Driver::new(io::Driver::new());
+        let handle = timer.handle();
+        let pool = ThreadPool::new(1, Parker::new(timer));
+        let spawner = pool.spawner();
+
+        let blocking = pool.spawn(async move {
+            let (tx, rx) = mpsc::channel();
+            spawner.spawn(async move {
+                time::sleep(&handle, Duration::from_millis(20)).await;
+                tx.send(()).unwrap();
+            });
+            block_in_place(|| rx.recv_timeout(Duration::from_secs(5)))
+        });
+        assert_eq!(pool.block_on(blocking).unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn block_in_place_takes_the_worker_back() {
+        let pool = thread_pool(1);
+
+        let handle = pool.spawn(async {
+            let mut taken_back = 0;
+            for _ in 0..20 {
+                let before = thread::current().id();
+                // Returns before the new thread could start running the worker.
+                block_in_place(|| ());
+                yield_now().await;
+                if thread::current().id() == before {
+                    taken_back += 1;
+                }
+            }
+            taken_back
+        });
+        assert!(pool.block_on(handle).unwrap() > 0);
+        assert_eq!(pool.block_on(pool.spawn(async { 1 })).unwrap(), 1);
+    }
+
+    #[test]
+    fn shutdown_with_a_worker_on_a_handoff_thread() {
+        let pool = thread_pool(1);
+        let (started, blocked) = mpsc::channel();
+
+        let handle = pool.spawn(async move {
+            block_in_place(|| {
+                started.send(()).unwrap();
+                thread::sleep(Duration::from_millis(100));
+            });
+        });
+        blocked.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        let (done, dropped) = mpsc::channel();
+        thread::spawn(move || {
+            drop(pool);
+            done.send(()).unwrap();
+        });
+        dropped.recv_timeout(Duration::from_secs(5)).unwrap();
+        futures::executor::block_on(handle).unwrap();
+    }
+}
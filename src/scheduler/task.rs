@@ -0,0 +1,329 @@
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::mem::{self, ManuallyDrop};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{
+    atomic::{fence, AtomicBool, Ordering},
+    Arc, Mutex, TryLockError,
+};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use futures::task::AtomicWaker;
+use pin_project_lite::pin_project;
+use slab::Slab;
+
+const VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |clone: *const ()| unsafe {
+        Arc::increment_strong_count(clone as *const Task);
+        RawWaker::new(clone, &VTABLE)
+    },
+    |wake: *const ()| unsafe {
+        Task::schedule(Arc::from_raw(wake as *const Task));
+    },
+    |wake_by_ref: *const ()| unsafe {
+        let arc = ManuallyDrop::new(Arc::from_raw(wake_by_ref as *const Task));
+        Task::schedule((*arc).clone());
+    },
+    |drop_waker: *const ()| unsafe {
+        drop(Arc::from_raw(drop_waker as *const Task));
+    },
+);
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Receives tasks that have been woken and must be polled again.
+pub(crate) trait Schedule: Send + Sync + 'static {
+    fn schedule(&self, task: Arc<Task>);
+}
+
+pub(crate) struct Task {
+    id: usize,
+    future: Mutex<Option<BoxFuture>>,
+    scheduled: AtomicBool,
+    /// Set by `cancel`, the future is dropped by `run` if it was being polled.
+    cancelled: AtomicBool,
+    scheduler: Arc<dyn Schedule>,
+}
+
+impl Task {
+    /// Polls the task once, returns true if it has completed.
+    pub(crate) fn run(self: &Arc<Self>) -> bool {
+        self.scheduled.store(false, Ordering::Release);
+
+        let raw_waker = RawWaker::new(&**self as *const Task as *const (), &VTABLE);
+        let waker = ManuallyDrop::new(unsafe { Waker::from_raw(raw_waker) });
+        let mut cx = Context::from_waker(&waker);
+
+        let mut future = self.future.lock().expect("task future poisoned");
+        let done = match future.as_mut() {
+            Some(f) => f.as_mut().poll(&mut cx).is_ready(),
+            None => true,
+        };
+        if done {
+            *future = None;
+        }
+        drop(future);
+
+        fence(Ordering::SeqCst);
+        if self.cancelled.load(Ordering::Relaxed) {
+            self.cancel();
+        }
+        done
+    }
+
+    fn schedule(self: Arc<Self>) {
+        if !self.scheduled.swap(true, Ordering::AcqRel) {
+            let scheduler = self.scheduler.clone();
+            scheduler.schedule(self);
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+
+        // The task is being polled, possibly by the caller when it drops its own scheduler,
+        // `run` drops the future once the poll returns.
+        let future = match self.future.try_lock() {
+            Ok(mut future) => future.take(),
+            Err(TryLockError::WouldBlock) => return,
+            Err(TryLockError::Poisoned(_)) => panic!("task future poisoned"),
+        };
+        // Dropping the harness outside of the lock lets it wake other tasks.
+        drop(future);
+    }
+}
+
+/// Every task spawned on a scheduler that has not completed yet, so that they can be
+/// cancelled when the scheduler goes away.
+pub(crate) struct OwnedTasks {
+    tasks: Mutex<Slab<Arc<Task>>>,
+    closed: AtomicBool,
+}
+
+impl OwnedTasks {
+    pub(crate) fn new() -> Self {
+        Self {
+            tasks: Mutex::new(Slab::new()),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Allocates a task for `future`, the task is returned already marked as scheduled and
+    /// must be handed to the scheduler by the caller.
+    pub(crate) fn bind<F>(
+        &self,
+        future: F,
+        scheduler: Arc<dyn Schedule>,
+    ) -> (Option<Arc<Task>>, JoinHandle<F::Output>)
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (harness, handle) = harness(future);
+
+        if self.closed.load(Ordering::Acquire) {
+            return (None, handle);
+        }
+
+        let mut tasks = self.tasks.lock().expect("owned tasks poisoned");
+        let entry = tasks.vacant_entry();
+        let task = Arc::new(Task {
+            id: entry.key(),
+            future: Mutex::new(Some(Box::pin(harness))),
+            scheduled: AtomicBool::new(true),
+            cancelled: AtomicBool::new(false),
+            scheduler,
+        });
+        entry.insert(task.clone());
+        (Some(task), handle)
+    }
+
+    pub(crate) fn release(&self, task: &Arc<Task>) {
+        let mut tasks = self.tasks.lock().expect("owned tasks poisoned");
+        // A leftover waker can run a completed task again, its key may belong to a new task.
+        let owned = tasks
+            .get(task.id)
+            .is_some_and(|owned| Arc::ptr_eq(owned, task));
+        if owned {
+            drop(tasks.remove(task.id));
+        }
+    }
+
+    /// Cancels every task that is still alive, the join handles will resolve to
+    /// `JoinError::Cancelled`.
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        let mut tasks = mem::take(&mut *self.tasks.lock().expect("owned tasks poisoned"));
+        for task in tasks.drain() {
+            task.cancel();
+        }
+    }
+}
+
+pub enum JoinError {
+    Cancelled,
+    Panic(Box<dyn Any + Send + 'static>),
+}
+
+impl JoinError {
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Self::Cancelled)
+    }
+    pub fn is_panic(&self) -> bool {
+        matches!(self, Self::Panic(_))
+    }
+    pub fn into_panic(self) -> Box<dyn Any + Send + 'static> {
+        match self {
+            Self::Panic(payload) => payload,
+            Self::Cancelled => panic!("`JoinError::into_panic` called on a cancelled task"),
+        }
+    }
+}
+
+impl fmt::Debug for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "JoinError::Cancelled"),
+            Self::Panic(_) => write!(f, "JoinError::Panic(..)"),
+        }
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "task was cancelled"),
+            Self::Panic(_) => write!(f, "task panicked"),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+impl From<JoinError> for io::Error {
+    fn from(err: JoinError) -> io::Error {
+        io::Error::other(err.to_string())
+    }
+}
+
+struct Join<T> {
+    output: Mutex<Option<Result<T, JoinError>>>,
+    complete: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl<T> Join<T> {
+    fn complete(&self, output: Result<T, JoinError>) {
+        if self.complete.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        *self.output.lock().expect("join output poisoned") = Some(output);
+        self.waker.wake();
+    }
+}
+
+/// Resolves to the output of a spawned task.
+pub struct JoinHandle<T> {
+    join: Arc<Join<T>>,
+    /// Set once the output has been returned, the handle must not be polled again.
+    done: bool,
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        assert!(!self.done, "`JoinHandle` polled after completion");
+
+        self.join.waker.register(cx.waker());
+        let output = self
+            .join
+            .output
+            .lock()
+            .expect("join output poisoned")
+            .take();
+        match output {
+            Some(output) => {
+                self.done = true;
+                Poll::Ready(output)
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Marks the task as cancelled if it is dropped before completing.
+struct Completion<T>(Arc<Join<T>>);
+
+impl<T> Drop for Completion<T> {
+    fn drop(&mut self) {
+        self.0.complete(Err(JoinError::Cancelled))
+    }
+}
+
+pin_project! {
+    /// Drives the user future and forwards its output (or panic) to the `JoinHandle`.
+    pub(crate) struct Harness<F: Future> {
+        #[pin]
+        future: F,
+        join: Completion<F::Output>,
+    }
+}
+
+pub(crate) fn harness<F: Future>(future: F) -> (Harness<F>, JoinHandle<F::Output>) {
+    let (join, handle) = join();
+    (Harness { future, join }, handle)
+}
+
+/// Wraps a closure run by the blocking pool, its output (or panic) is forwarded to the
+/// `JoinHandle`. The handle resolves to `JoinError::Cancelled` if the closure is dropped
+/// without being run.
+pub(crate) fn blocking<F, T>(f: F) -> (impl FnOnce() + Send + 'static, JoinHandle<T>)
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (join, handle) = join();
+    let run = move || {
+        let output = panic::catch_unwind(AssertUnwindSafe(f)).map_err(JoinError::Panic);
+        join.0.complete(output);
+    };
+    (run, handle)
+}
+
+fn join<T>() -> (Completion<T>, JoinHandle<T>) {
+    let join = Arc::new(Join {
+        output: Mutex::new(None),
+        complete: AtomicBool::new(false),
+        waker: AtomicWaker::new(),
+    });
+    let handle = JoinHandle {
+        join: join.clone(),
+        done: false,
+    };
+    (Completion(join), handle)
+}
+
+impl<F: Future> Future for Harness<F> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let me = self.project();
+        let future = me.future;
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => {
+                me.join.0.complete(Ok(output));
+                Poll::Ready(())
+            }
+            Err(panic) => {
+                me.join.0.complete(Err(JoinError::Panic(panic)));
+                Poll::Ready(())
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+mod worker;
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::mem;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use std::thread;
+use std::time::Duration;
+
+use super::task::{OwnedTasks, Schedule, Task};
+use super::JoinHandle;
+use crate::blocking::{
+    BlockingPool, DEFAULT_KEEP_ALIVE, DEFAULT_MAX_THREADS, DEFAULT_SHUTDOWN_TIMEOUT,
+};
+use crate::park::{Park, Parker, Unpark};
+use crate::util::ThreadConfig;
+
+pub use worker::block_in_place;
+use worker::Worker;
+
+/// Multi-threaded scheduler, every worker owns a clone of the same `Parker` so that only one
+/// of them turns the IO driver while the others sleep.
+pub struct ThreadPool {
+    shared: Arc<Shared>,
+    shutdown_timeout: Duration,
+}
+
+/// Cloneable handle used to spawn tasks on a `ThreadPool`.
+#[derive(Clone)]
+pub struct Spawner {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    remotes: Box<[Remote]>,
+    inject: Mutex<VecDeque<Arc<Task>>>,
+    idle: Mutex<Vec<usize>>,
+    owned: OwnedTasks,
+    shutdown: AtomicBool,
+    threads: Mutex<Vec<thread::JoinHandle<()>>>,
+    blocking: BlockingPool,
+    config: ThreadConfig,
+}
+
+/// Part of a worker that is reachable from the other threads.
+struct Remote {
+    queue: Mutex<VecDeque<Arc<Task>>>,
+    unpark: Box<dyn Unpark>,
+}
+
+impl ThreadPool {
+    pub fn new<D>(size: usize, park: Parker<D>) -> Self
+    where
+        D: Park + Send + 'static,
+    {
+        let blocking =
+            BlockingPool::new("executor-blocking", DEFAULT_MAX_THREADS, DEFAULT_KEEP_ALIVE);
+        Self::with_blocking_pool(size, park, blocking)
+    }
+
+    /// Uses `blocking` to run the closures given to `spawn_blocking`, it is shut down when
+    /// the pool is dropped.
+    pub fn with_blocking_pool<D>(size: usize, park: Parker<D>, blocking: BlockingPool) -> Self
+    where
+        D: Park + Send + 'static,
+    {
+        Self::with_threads(size, park, blocking, ThreadConfig::new("executor"))
+    }
+
+    /// Workers are started with `threads` and named `{name}-worker-{index}`.
+    pub fn with_threads<D>(
+        size: usize,
+        park: Parker<D>,
+        blocking: BlockingPool,
+        threads: ThreadConfig,
+    ) -> Self
+    where
+        D: Park + Send + 'static,
+    {
+        assert!(size > 0, "a thread pool needs at least one worker");
+
+        let mut parkers: Vec<Parker<D>> = (1..size).map(|_| park.clone()).collect();
+        parkers.insert(0, park);
+
+        let remotes = parkers
+            .iter()
+            .map(|park| Remote {
+                queue: Mutex::new(VecDeque::new()),
+                unpark: Box::new(park.handle()) as Box<dyn Unpark>,
+            })
+            .collect();
+
+        let shared = Arc::new(Shared {
+            remotes,
+            inject: Mutex::new(VecDeque::new()),
+            idle: Mutex::new(Vec::with_capacity(size)),
+            owned: OwnedTasks::new(),
+            shutdown: AtomicBool::new(false),
+            threads: Mutex::new(Vec::with_capacity(size)),
+            blocking,
+            config: threads,
+        });
+
+        let mut threads = shared.threads.lock().unwrap();
+        for (index, park) in parkers.into_iter().enumerate() {
+            let worker = Box::new(Worker::new(shared.clone(), index, park));
+            let thread = shared
+                .config
+                .spawn(shared.worker_name(index), move || worker.run())
+                .expect("couldn't spawn worker thread");
+            threads.push(thread);
+        }
+        drop(threads);
+
+        Self {
+            shared,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        }
+    }
+
+    /// Drops the pool, waiting up to `timeout` instead of `DEFAULT_SHUTDOWN_TIMEOUT` for the
+    /// blocking closures to finish.
+    pub fn shutdown_timeout(mut self, timeout: Duration) {
+        self.shutdown_timeout = timeout;
+    }
+
+    pub fn spawner(&self) -> Spawner {
+        Spawner {
+            shared: self.shared.clone(),
+        }
+    }
+
+    pub fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.shared.spawn(&self.shared, f)
+    }
+
+    /// Runs `f` on the blocking pool instead of a worker.
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.shared.blocking.spawn(f)
+    }
+
+    /// Blocks the current thread on `f`, the workers keep driving IO and spawned tasks.
+    pub fn block_on<F: Future>(&self, f: F) -> F::Output {
+        futures::executor::block_on(f)
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        for remote in self.shared.remotes.iter() {
+            remote.unpark.unpark();
+        }
+
+        // Workers moved by `block_in_place` add their new thread while we join the others.
+        loop {
+            let threads = mem::take(&mut *self.shared.threads.lock().unwrap());
+            if threads.is_empty() {
+                break;
+            }
+            // A worker dropping the pool can't wait for itself, it stops once its task returns.
+            let current = thread::current().id();
+            for thread in threads {
+                if thread.thread().id() != current {
+                    let _ = thread.join();
+                }
+            }
+        }
+
+        self.shared.owned.close();
+        drop(mem::take(&mut *self.shared.inject.lock().unwrap()));
+        for remote in self.shared.remotes.iter() {
+            drop(mem::take(&mut *remote.queue.lock().unwrap()));
+        }
+        // Let the blocking closures finish, they may still use the drivers.
+        self.shared.blocking.shutdown(Some(self.shutdown_timeout));
+    }
+}
+
+impl Spawner {
+    pub fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.shared.spawn(&self.shared, f)
+    }
+
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.shared.blocking.spawn(f)
+    }
+}
+
+impl Shared {
+    fn spawn<F>(&self, me: &Arc<Shared>, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, handle) = self.owned.bind(f, me.clone());
+        if let Some(task) = task {
+            self.schedule(task);
+        }
+        handle
+    }
+
+    fn worker_name(&self, index: usize) -> String {
+        format!("{}-worker-{}", self.config.name(), index)
+    }
+
+    /// Wakes up one sleeping worker, if any.
+    fn notify_parked(&self) {
+        let index = self.idle.lock().unwrap().pop();
+        if let Some(index) = index {
+            self.remotes[index].unpark.unpark();
+        }
+    }
+}
+
+impl Schedule for Shared {
+    fn schedule(&self, task: Arc<Task>) {
+        match worker::current(self) {
+            Some(index) => self.remotes[index].queue.lock().unwrap().push_back(task),
+            None => self.inject.lock().unwrap().push_back(task),
+        }
+        self.notify_parked();
+    }
+}
@@ -0,0 +1,288 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::sync::{atomic::Ordering, Arc, Mutex};
+use std::time::Duration;
+
+use super::Shared;
+use crate::park::{Park, Parker};
+use crate::scheduler::task::Task;
+
+/// Number of ticks after which the injection queue is checked before the local one.
+const GLOBAL_POLL_INTERVAL: u32 = 31;
+/// Number of ticks after which a busy worker gives the IO driver a turn.
+const DRIVER_POLL_INTERVAL: u32 = 61;
+
+thread_local! {
+    static CURRENT: Cell<Option<(*const Shared, usize)>> = const { Cell::new(None) };
+    /// Worker lent to the task running on this thread, `block_in_place` moves it to another
+    /// thread.
+    static CORE: RefCell<Option<Box<dyn Core>>> = const { RefCell::new(None) };
+}
+
+/// Worker with its driver type erased, so that `block_in_place` can move it.
+trait Core: Send {
+    fn shared(&self) -> &Arc<Shared>;
+    fn index(&self) -> usize;
+    fn run(self: Box<Self>);
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+/// Runs `f`, a blocking closure, on the current thread.
+///
+/// When called from a task of a `ThreadPool`, the worker running it is first handed to a new
+/// thread: its queued tasks keep running and the driver keeps being turned while `f` blocks.
+/// The worker is taken back afterwards if that thread did not start running it yet, otherwise
+/// this thread finishes the task and exits. Outside of a worker `f` is simply called.
+pub fn block_in_place<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let core = match CORE.with(|core| core.borrow_mut().take()) {
+        Some(core) => core,
+        None => return f(),
+    };
+
+    let shared = core.shared().clone();
+    let index = core.index();
+    let handoff = Arc::new(Mutex::new(Some(core)));
+    let theirs = handoff.clone();
+
+    let mut threads = shared.threads.lock().unwrap();
+    let spawned = shared.config.spawn(shared.worker_name(index), move || {
+        let core = theirs.lock().unwrap().take();
+        if let Some(core) = core {
+            core.run();
+        }
+    });
+    // Without a new thread the worker stays blocked with this one.
+    if let Ok(thread) = spawned {
+        threads.retain(|thread| !thread.is_finished());
+        threads.push(thread);
+    }
+    drop(threads);
+
+    let output = f();
+
+    let core = handoff.lock().unwrap().take();
+    if let Some(core) = core {
+        CORE.with(|slot| *slot.borrow_mut() = Some(core));
+    }
+    output
+}
+
+/// Returns the index of the worker running on this thread if it belongs to `shared`.
+pub(super) fn current(shared: &Shared) -> Option<usize> {
+    CURRENT.with(|current| match current.get() {
+        Some((ptr, index)) if std::ptr::eq(ptr, shared) => Some(index),
+        _ => None,
+    })
+}
+
+pub(super) struct Worker<D: Park> {
+    shared: Arc<Shared>,
+    index: usize,
+    park: Parker<D>,
+    tick: u32,
+}
+
+impl<D> Worker<D>
+where
+    D: Park + Send + 'static,
+{
+    pub(super) fn new(shared: Arc<Shared>, index: usize, park: Parker<D>) -> Self {
+        Self {
+            shared,
+            index,
+            park,
+            tick: 0,
+        }
+    }
+
+    pub(super) fn run(mut self: Box<Self>) {
+        CURRENT.with(|current| current.set(Some((&*self.shared as *const Shared, self.index))));
+
+        while !self.shared.shutdown.load(Ordering::Acquire) {
+            self.tick = self.tick.wrapping_add(1);
+
+            if self.tick.is_multiple_of(DRIVER_POLL_INTERVAL) {
+                self.park
+                    .park_timeout(Duration::from_millis(0))
+                    .expect("couldn't poll the driver");
+            }
+
+            match self.next_task().or_else(|| self.steal()) {
+                Some(task) => match self.run_task(task) {
+                    Some(worker) => self = worker,
+                    // Moved to another thread by `block_in_place`.
+                    None => break,
+                },
+                None => self.park(),
+            }
+        }
+
+        CURRENT.with(|current| current.set(None));
+    }
+
+    /// Lends the worker to the task while it runs, returns it unless `block_in_place` took it.
+    fn run_task(self: Box<Self>, task: Arc<Task>) -> Option<Box<Self>> {
+        let shared = self.shared.clone();
+        CORE.with(|core| *core.borrow_mut() = Some(self));
+
+        if task.run() {
+            shared.owned.release(&task);
+        }
+
+        let core = CORE.with(|core| core.borrow_mut().take())?;
+        let worker = core
+            .into_any()
+            .downcast()
+            .unwrap_or_else(|_| unreachable!("a worker of another pool was lent to the thread"));
+        Some(worker)
+    }
+
+    fn queue(&self) -> &std::sync::Mutex<VecDeque<Arc<Task>>> {
+        &self.shared.remotes[self.index].queue
+    }
+
+    fn next_task(&self) -> Option<Arc<Task>> {
+        let local = || self.queue().lock().unwrap().pop_front();
+        let global = || self.shared.inject.lock().unwrap().pop_front();
+
+        if self.tick.is_multiple_of(GLOBAL_POLL_INTERVAL) {
+            global().or_else(local)
+        } else {
+            local().or_else(global)
+        }
+    }
+
+    /// Takes half of the run queue of another worker, the victims are visited starting from a
+    /// position that changes on every tick.
+    fn steal(&self) -> Option<Arc<Task>> {
+        let remotes = &self.shared.remotes;
+        let start = self.tick as usize % remotes.len();
+
+        for i in 0..remotes.len() {
+            let victim = (start + i) % remotes.len();
+            if victim == self.index {
+                continue;
+            }
+
+            let mut stolen = {
+                let mut queue = remotes[victim].queue.lock().unwrap();
+                let n = queue.len() - queue.len() / 2;
+                if n == 0 {
+                    continue;
+                }
+                let at = queue.len() - n;
+                queue.split_off(at)
+            };
+
+            let task = stolen.pop_front();
+            self.queue().lock().unwrap().append(&mut stolen);
+            return task;
+        }
+        None
+    }
+
+    fn has_work(&self) -> bool {
+        !self.queue().lock().unwrap().is_empty() || !self.shared.inject.lock().unwrap().is_empty()
+    }
+
+    fn park(&mut self) {
+        let all_idle = {
+            let mut idle = self.shared.idle.lock().unwrap();
+            idle.push(self.index);
+            idle.len() == self.shared.remotes.len()
+        };
+
+        // Work might have been pushed before we were registered as idle.
+        if !self.has_work() && !self.shared.shutdown.load(Ordering::Acquire) {
+            // Only the last worker to go idle knows that the whole pool has nothing to run.
+            if all_idle {
+                self.park.park_idle().expect("couldn't park worker");
+            } else {
+                self.park.park().expect("couldn't park worker");
+            }
+        }
+
+        self.shared
+            .idle
+            .lock()
+            .unwrap()
+            .retain(|&i| i != self.index);
+
+        // We may have been the one turning the driver, hand it over to a sleeping worker while
+        // we process what it produced.
+        if self.has_work() {
+            self.shared.notify_parked();
+        }
+    }
+}
+
+impl<D> Core for Worker<D>
+where
+    D: Park + Send + 'static,
+{
+    fn shared(&self) -> &Arc<Shared> {
+        &self.shared
+    }
+    fn index(&self) -> usize {
+        self.index
+    }
+    fn run(self: Box<Self>) {
+        Worker::run(self)
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::{atomic::AtomicBool, Arc, Mutex};
+
+    use super::Worker;
+    use crate::blocking::BlockingPool;
+    use crate::io;
+    use crate::park::{Park, Parker, Unpark};
+    use crate::scheduler::task::OwnedTasks;
+    use crate::scheduler::thread_pool::{Remote, Shared};
+    use crate::util::ThreadConfig;
+
+    #[test]
+    fn steal_takes_half_of_the_victim_queue() {
+        let park = Parker::new(io::Driver::new());
+        let remotes = (0..2)
+            .map(|_| Remote {
+                queue: Mutex::new(VecDeque::new()),
+                unpark: Box::new(park.handle()) as Box<dyn Unpark>,
+            })
+            .collect();
+        let shared = Arc::new(Shared {
+            remotes,
+            inject: Mutex::new(VecDeque::new()),
+            idle: Mutex::new(Vec::new()),
+            owned: OwnedTasks::new(),
+            shutdown: AtomicBool::new(false),
+            threads: Mutex::new(Vec::new()),
+            blocking: BlockingPool::new("test-blocking", 1, Default::default()),
+            config: ThreadConfig::new("test"),
+        });
+
+        let mut victim = shared.remotes[1].queue.lock().unwrap();
+        for _ in 0..7 {
+            let (task, _) = shared.owned.bind(async {}, shared.clone());
+            victim.push_back(task.unwrap());
+        }
+        drop(victim);
+        let worker = Worker::new(shared.clone(), 0, park);
+
+        // Runs one of the four stolen tasks right away.
+        assert!(worker.steal().is_some());
+        assert_eq!(shared.remotes[0].queue.lock().unwrap().len(), 3);
+        assert_eq!(shared.remotes[1].queue.lock().unwrap().len(), 3);
+        shared.owned.close();
+    }
+}
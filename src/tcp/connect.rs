@@ -0,0 +1,115 @@
+use std::{
+    error::Error, fmt, future::Future, io, net::SocketAddr, pin::Pin, task::Poll, time::Duration,
+};
+
+use super::TcpStream;
+use crate::io::Handle;
+use crate::time;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+/// Delay before starting the next connection attempt while the previous ones are still
+/// pending, RFC 8305 recommends 250ms.
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Every connection attempt made by `connect` failed.
+#[derive(Debug)]
+pub struct ConnectError {
+    errors: Vec<(SocketAddr, io::Error)>,
+}
+
+/// Connects to the first reachable address of `addrs` using happy eyeballs (RFC 8305).
+///
+/// IPv6 and IPv4 candidates are interleaved, starting with the family of the first address.
+/// A new attempt is started every `ATTEMPT_DELAY` or as soon as one fails, the first stream
+/// to connect is returned and the other attempts are dropped. If all of them fail the error
+/// wraps a `ConnectError` listing each failure.
+///
+/// Host names are resolved beforehand without blocking the executor, for instance with
+/// `dns::Resolver::lookup_host`.
+pub async fn connect(
+    handle: Handle,
+    timer: &time::Handle,
+    addrs: impl IntoIterator<Item = SocketAddr>,
+) -> io::Result<TcpStream> {
+    let mut candidates = interleave(addrs.into_iter().collect()).into_iter();
+    if candidates.len() == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no address to connect to",
+        ));
+    }
+
+    let mut attempts = FuturesUnordered::new();
+    let mut delay = time::sleep(timer, ATTEMPT_DELAY);
+    let mut errors = Vec::new();
+    let mut failed = false;
+
+    futures::future::poll_fn(|cx| loop {
+        if failed || attempts.is_empty() || Pin::new(&mut delay).poll(cx).is_ready() {
+            match candidates.next() {
+                Some(addr) => {
+                    attempts.push(attempt(handle.clone(), addr));
+                    delay.reset(timer.now() + ATTEMPT_DELAY);
+                    failed = false;
+                    continue;
+                }
+                None if attempts.is_empty() => {
+                    let errors = std::mem::take(&mut errors);
+                    return Poll::Ready(Err(io::Error::other(ConnectError { errors })));
+                }
+                None => {}
+            }
+        }
+
+        match attempts.poll_next_unpin(cx) {
+            Poll::Ready(Some((_, Ok(stream)))) => return Poll::Ready(Ok(stream)),
+            Poll::Ready(Some((addr, Err(e)))) => {
+                errors.push((addr, e));
+                failed = true;
+            }
+            Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+        }
+    })
+    .await
+}
+
+async fn attempt(handle: Handle, addr: SocketAddr) -> (SocketAddr, io::Result<TcpStream>) {
+    (addr, TcpStream::connect(handle, addr).await)
+}
+
+/// Alternates address families, keeping the resolver's order within each family.
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_v6 = matches!(addrs.first(), Some(SocketAddr::V6(_)));
+    let (mut first, mut second): (Vec<_>, Vec<_>) =
+        addrs.into_iter().partition(|a| a.is_ipv6() == first_v6);
+
+    let mut out = Vec::with_capacity(first.len() + second.len());
+    let (mut first, mut second) = (first.drain(..), second.drain(..));
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => return out,
+            (a, b) => out.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+impl ConnectError {
+    /// Address and error of each failed attempt, in the order they failed.
+    pub fn errors(&self) -> &[(SocketAddr, io::Error)] {
+        &self.errors
+    }
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not connect to any address")?;
+        for (i, (addr, e)) in self.errors.iter().enumerate() {
+            let sep = if i == 0 { ": " } else { ", " };
+            write!(f, "{}{} ({})", sep, addr, e)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConnectError {}
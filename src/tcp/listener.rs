@@ -0,0 +1,69 @@
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use super::TcpStream;
+use crate::io::{Handle, PollEvented, Ready};
+
+use futures::{ready, stream::Stream};
+
+pub struct TcpListener {
+    io: PollEvented<mio::net::TcpListener>,
+    handle: Handle,
+}
+
+/// Stream of the connections accepted by a `TcpListener`.
+pub struct Incoming<'a> {
+    listener: &'a TcpListener,
+}
+
+impl TcpListener {
+    pub fn new(handle: Handle, listener: mio::net::TcpListener) -> io::Result<TcpListener> {
+        let io = PollEvented::new(handle.clone(), listener)?;
+        Ok(TcpListener { io, handle })
+    }
+
+    pub fn bind(handle: Handle, addr: SocketAddr) -> io::Result<Self> {
+        let sys = mio::net::TcpListener::bind(addr)?;
+        Self::new(handle, sys)
+    }
+
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        futures::future::poll_fn(|cx| self.poll_accept(cx)).await
+    }
+
+    pub fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        ready!(self.io.poll_read_ready(cx, Ready::readable()))?;
+
+        match self.io.get_ref().accept() {
+            Ok((io, addr)) => {
+                Poll::Ready(TcpStream::new(self.handle.clone(), io).map(|s| (s, addr)))
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(cx, Ready::readable())?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming { listener: self }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<TcpStream>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let (stream, _) = ready!(self.listener.poll_accept(cx))?;
+        Poll::Ready(Some(Ok(stream)))
+    }
+}
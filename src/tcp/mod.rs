@@ -0,0 +1,13 @@
+mod connect;
+mod listener;
+mod socket;
+mod split;
+mod split_owned;
+mod stream;
+
+pub use connect::{connect, ConnectError};
+pub use listener::{Incoming, TcpListener};
+pub use socket::TcpSocket;
+pub use split::{ReadHalf, WriteHalf};
+pub use split_owned::{OwnedReadHalf, OwnedWriteHalf, ReuniteError};
+pub use stream::TcpStream;
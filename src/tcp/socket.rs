@@ -0,0 +1,82 @@
+use std::{io, net::SocketAddr};
+
+use super::{TcpListener, TcpStream};
+use crate::io::Handle;
+
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+
+/// TCP socket that has not been connected or turned into a listener yet, sets the options
+/// that must be applied before `connect` or `listen`.
+pub struct TcpSocket {
+    inner: Socket,
+}
+
+impl TcpSocket {
+    pub fn new_v4() -> io::Result<Self> {
+        Self::new(Domain::IPV4)
+    }
+    pub fn new_v6() -> io::Result<Self> {
+        Self::new(Domain::IPV6)
+    }
+    /// Creates a socket of the same family as `addr`.
+    pub fn new_for_addr(addr: SocketAddr) -> io::Result<Self> {
+        Self::new(Domain::for_address(addr))
+    }
+
+    fn new(domain: Domain) -> io::Result<Self> {
+        let inner = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?;
+        inner.set_nonblocking(true)?;
+        Ok(Self { inner })
+    }
+
+    pub fn set_reuseaddr(&self, reuseaddr: bool) -> io::Result<()> {
+        self.inner.set_reuse_address(reuseaddr)
+    }
+    pub fn reuseaddr(&self) -> io::Result<bool> {
+        self.inner.reuse_address()
+    }
+    pub fn set_reuseport(&self, reuseport: bool) -> io::Result<()> {
+        self.inner.set_reuse_port(reuseport)
+    }
+    pub fn reuseport(&self) -> io::Result<bool> {
+        self.inner.reuse_port()
+    }
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.inner.set_send_buffer_size(size)
+    }
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        self.inner.send_buffer_size()
+    }
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.inner.set_recv_buffer_size(size)
+    }
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        self.inner.recv_buffer_size()
+    }
+
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<()> {
+        self.inner.bind(&SockAddr::from(addr))
+    }
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner
+            .local_addr()?
+            .as_socket()
+            .ok_or_else(|| io::Error::other("socket is not an inet socket"))
+    }
+
+    pub async fn connect(self, handle: Handle, addr: SocketAddr) -> io::Result<TcpStream> {
+        match self.inner.connect(&SockAddr::from(addr)) {
+            Ok(()) => {}
+            Err(ref e) if e.raw_os_error() == Some(libc::EINPROGRESS) => {}
+            Err(e) => return Err(e),
+        }
+        let sys = mio::net::TcpStream::from_std(self.inner.into());
+        TcpStream::connected(handle, sys).await
+    }
+
+    pub fn listen(self, handle: Handle, backlog: u32) -> io::Result<TcpListener> {
+        self.inner.listen(backlog as i32)?;
+        let sys = mio::net::TcpListener::from_std(self.inner.into());
+        TcpListener::new(handle, sys)
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    io::{self, Read, Write},
+    mem::ManuallyDrop,
+    net::{self, SocketAddr},
+    os::unix::io::{AsRawFd, FromRawFd, IntoRawFd},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use super::split::{split, ReadHalf, WriteHalf};
+use super::split_owned::{split_owned, OwnedReadHalf, OwnedWriteHalf};
+use crate::io::{Handle, Interest, PollEvented, Ready};
+use crate::time;
+
+use futures::{
+    io::{AsyncRead, AsyncWrite},
+    ready,
+};
+
+use tokio::io::{AsyncRead as TokioAsyncRead, AsyncWrite as TokioAsyncWrite};
+
+pub struct TcpStream {
+    io: PollEvented<mio::net::TcpStream>,
+}
+
+impl TcpStream {
+    /// Registers the stream for both directions, the split halves only get a shared
+    /// reference to it and could not add an interest later.
+    pub fn new(handle: Handle, connected: mio::net::TcpStream) -> io::Result<TcpStream> {
+        let io = PollEvented::new_with_interest(handle, connected, Interest::both())?;
+        Ok(TcpStream { io })
+    }
+
+    /// Registers a connected std stream, it is switched to non-blocking mode.
+    pub fn from_std(handle: Handle, stream: net::TcpStream) -> io::Result<TcpStream> {
+        stream.set_nonblocking(true)?;
+        Self::new(handle, mio::net::TcpStream::from_std(stream))
+    }
+
+    /// Deregisters the stream, the returned std stream is still in non-blocking mode.
+    pub fn into_std(self) -> io::Result<net::TcpStream> {
+        let sys = self.io.into_inner()?;
+        Ok(unsafe { net::TcpStream::from_raw_fd(sys.into_raw_fd()) })
+    }
+
+    pub async fn connect(handle: Handle, addr: SocketAddr) -> io::Result<Self> {
+        let sys = mio::net::TcpStream::connect(addr)?;
+        Self::connected(handle, sys).await
+    }
+
+    /// Fails with `TimedOut` if the connection is not established within `timeout`.
+    pub async fn connect_timeout(
+        handle: Handle,
+        timer: &time::Handle,
+        addr: SocketAddr,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        time::timeout(timer, timeout, Self::connect(handle, addr)).await?
+    }
+
+    /// Waits for the connection initiated on `sys` to be established.
+    pub(super) async fn connected(handle: Handle, sys: mio::net::TcpStream) -> io::Result<Self> {
+        // Writability is how the connection is reported as established.
+        let io = PollEvented::new_with_interest(handle, sys, Interest::both())?;
+        let stream = TcpStream { io };
+
+        futures::future::poll_fn(|cx| stream.io.poll_write_ready(cx)).await?;
+
+        if let Some(e) = stream.io.get_ref().take_error()? {
+            return Err(e);
+        }
+
+        Ok(stream)
+    }
+
+    /// Receives data without removing it from the queue.
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.poll_peek(cx, buf)).await
+    }
+
+    pub fn poll_peek(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_read_ready(cx, Ready::readable()))?;
+
+        match self.io.get_ref().peek(buf) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(cx, Ready::readable())?;
+                Poll::Pending
+            }
+            x => Poll::Ready(x),
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+
+    /// Disables Nagle's algorithm when `nodelay` is set, small writes are sent right away.
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.io.get_ref().set_nodelay(nodelay)
+    }
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.io.get_ref().nodelay()
+    }
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.io.get_ref().set_ttl(ttl)
+    }
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.io.get_ref().ttl()
+    }
+
+    /// Enables keepalive probes after the connection has been idle for `keepalive`, `None`
+    /// disables them.
+    pub fn set_keepalive(&self, keepalive: Option<Duration>) -> io::Result<()> {
+        self.with_socket(|socket| match keepalive {
+            Some(time) => {
+                socket.set_tcp_keepalive(&socket2::TcpKeepalive::new().with_time(time))?;
+                socket.set_keepalive(true)
+            }
+            None => socket.set_keepalive(false),
+        })
+    }
+    pub fn keepalive(&self) -> io::Result<Option<Duration>> {
+        self.with_socket(|socket| {
+            if socket.keepalive()? {
+                socket.keepalive_time().map(Some)
+            } else {
+                Ok(None)
+            }
+        })
+    }
+    pub fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        self.with_socket(|socket| socket.set_linger(linger))
+    }
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        self.with_socket(|socket| socket.linger())
+    }
+
+    /// Splits the stream into halves borrowing it, a task can read while another one writes.
+    ///
+    /// `AsyncReadExt::split` takes precedence when it is in scope, call it as
+    /// `TcpStream::split(&mut stream)` then.
+    pub fn split(&mut self) -> (ReadHalf<'_>, WriteHalf<'_>) {
+        split(self)
+    }
+
+    /// Splits the stream into halves that can be moved to different tasks, `reunite` puts them
+    /// back together.
+    pub fn into_split(self) -> (OwnedReadHalf, OwnedWriteHalf) {
+        split_owned(self)
+    }
+
+    pub(super) fn poll_read_priv(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_read_ready(cx, Ready::readable()))?;
+
+        match self.io.get_ref().read(buf) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(cx, Ready::readable())?;
+                Poll::Pending
+            }
+            x => Poll::Ready(x),
+        }
+    }
+
+    pub(super) fn poll_write_priv(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_write_ready(cx))?;
+
+        match self.io.get_ref().write(buf) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_write_ready(cx)?;
+                Poll::Pending
+            }
+            x => Poll::Ready(x),
+        }
+    }
+
+    pub(super) fn shutdown_write(&self) -> io::Result<()> {
+        self.io.get_ref().shutdown(std::net::Shutdown::Write)
+    }
+
+    /// mio does not expose every socket option, the others are set through a socket borrowing
+    /// the file descriptor.
+    fn with_socket<R>(&self, f: impl FnOnce(&socket2::Socket) -> io::Result<R>) -> io::Result<R> {
+        let fd = self.io.get_ref().as_raw_fd();
+        let socket = ManuallyDrop::new(unsafe { socket2::Socket::from_raw_fd(fd) });
+        f(&socket)
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_read_priv(cx, buf)
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_priv(cx, buf)
+    }
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.shutdown_write())
+    }
+}
+
+impl TokioAsyncRead for TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        <Self as AsyncRead>::poll_read(self, cx, buf)
+    }
+}
+
+impl TokioAsyncWrite for TcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<Result<usize, tokio::io::Error>> {
+        <Self as AsyncWrite>::poll_write(self, cx, buf)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), tokio::io::Error>> {
+        <Self as AsyncWrite>::poll_flush(self, cx)
+    }
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), tokio::io::Error>> {
+        <Self as AsyncWrite>::poll_close(self, cx)
+    }
+}
@@ -0,0 +1,77 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of time used by the timer driver.
+///
+/// The clock follows `Instant::now()` until it is paused, from then on it only moves when
+/// `advance` is called or when the driver auto-advances it to the next timer because the
+/// scheduler has nothing left to run.
+#[derive(Clone)]
+pub struct Clock {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    /// Time of the clock when it was last paused or resumed.
+    base: Instant,
+    /// Real instant at which the clock was resumed, `None` while paused.
+    unfrozen: Option<Instant>,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                base: now,
+                unfrozen: Some(now),
+            })),
+        }
+    }
+
+    pub fn now(&self) -> Instant {
+        let inner = self.inner.lock().unwrap();
+        match inner.unfrozen {
+            Some(unfrozen) => inner.base + unfrozen.elapsed(),
+            None => inner.base,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.lock().unwrap().unfrozen.is_none()
+    }
+
+    pub fn pause(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(unfrozen) = inner.unfrozen.take() {
+            inner.base += unfrozen.elapsed();
+        }
+    }
+
+    pub fn resume(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.unfrozen.is_none() {
+            inner.unfrozen = Some(Instant::now());
+        }
+    }
+
+    /// Moves the paused clock forward, fails if the clock is not paused.
+    pub fn advance(&self, duration: Duration) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.unfrozen.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the clock must be paused to be advanced",
+            ));
+        }
+        inner.base += duration;
+        Ok(())
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
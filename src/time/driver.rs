@@ -0,0 +1,344 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use super::wheel::{Wheel, MAX_DURATION};
+use super::Clock;
+use crate::park::{Park, Unpark};
+
+use slab::Slab;
+
+/// Timer driver, wraps another `Park` implementation (usually the `io::Driver`) and bounds
+/// every park by the next timer deadline.
+///
+/// When the clock is paused the timers only bound parks in virtual time, the clock jumps to
+/// the next deadline when the scheduler parks through `park_idle` and polling the wrapped
+/// driver did not wake anything up.
+pub struct Driver<P> {
+    park: P,
+    inner: Arc<Inner>,
+    woken: Arc<AtomicBool>,
+}
+
+#[derive(Clone)]
+pub struct Handle {
+    inner: Weak<Inner>,
+}
+
+/// Unparks the wrapped driver and records the wake-up, so that a paused clock is not advanced
+/// while tasks are ready to run.
+pub struct Unparker<U> {
+    unpark: U,
+    woken: Arc<AtomicBool>,
+}
+
+pub(super) struct Inner {
+    clock: Clock,
+    start: Instant,
+    state: Mutex<State>,
+    unpark: Box<dyn Unpark>,
+}
+
+struct State {
+    wheel: Wheel,
+    entries: Slab<Entry>,
+    /// Tick at which the parked driver will wake up, `None` if it parks without timeout.
+    next_wake: Option<u64>,
+}
+
+struct Entry {
+    when: u64,
+    waker: Option<Waker>,
+    fired: bool,
+}
+
+impl<P: Park> Driver<P> {
+    pub fn new(park: P) -> Self {
+        Self::with_clock(park, Clock::new())
+    }
+
+    pub fn with_clock(park: P, clock: Clock) -> Self {
+        let unpark = park.handle();
+        Self {
+            park,
+            inner: Arc::new(Inner {
+                start: clock.now(),
+                clock,
+                state: Mutex::new(State {
+                    wheel: Wheel::new(),
+                    entries: Slab::new(),
+                    next_wake: None,
+                }),
+                unpark: Box::new(unpark),
+            }),
+            woken: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn handle(&self) -> Handle {
+        Handle {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    /// Time left until the next timer fires, if any.
+    fn next_timeout(&self) -> Option<Duration> {
+        let mut state = self.inner.state.lock().unwrap();
+        let next = state.wheel.next_expiration().map(|e| e.deadline);
+        state.next_wake = next;
+        drop(state);
+
+        next.map(|tick| {
+            let deadline = self.inner.start + Duration::from_millis(tick);
+            deadline.saturating_duration_since(self.inner.clock.now())
+        })
+    }
+
+    /// Polls the wrapped driver without blocking, then moves the paused clock forward by
+    /// `advance` if nothing was woken up in the meantime.
+    fn park_paused(&mut self, advance: Duration) -> io::Result<()> {
+        self.woken.store(false, Ordering::SeqCst);
+        self.park.park_timeout(Duration::from_millis(0))?;
+
+        if !self.woken.load(Ordering::SeqCst) {
+            self.inner.clock.advance(advance)?;
+        }
+        Ok(())
+    }
+
+    /// Fires every timer whose deadline has been reached.
+    fn process(&self) {
+        let now = self.inner.tick_for(self.inner.clock.now(), false);
+
+        let mut state = self.inner.state.lock().unwrap();
+        let expired = state.wheel.poll(now);
+        let wakers: Vec<Waker> = expired
+            .into_iter()
+            .filter_map(|key| {
+                let entry = &mut state.entries[key];
+                entry.fired = true;
+                entry.waker.take()
+            })
+            .collect();
+        state.next_wake = None;
+        drop(state);
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl<P: Park> Park for Driver<P> {
+    type Handle = Unparker<P::Handle>;
+
+    fn handle(&self) -> Self::Handle {
+        Unparker {
+            unpark: self.park.handle(),
+            woken: self.woken.clone(),
+        }
+    }
+    fn park(&mut self) -> io::Result<()> {
+        match self.next_timeout() {
+            // A paused clock only moves through `Handle::advance`, which unparks the driver.
+            Some(_) if self.inner.clock.is_paused() => self.park.park()?,
+            Some(timeout) => self.park.park_timeout(timeout)?,
+            None => self.park.park()?,
+        }
+        self.process();
+        Ok(())
+    }
+    fn park_timeout(&mut self, dur: Duration) -> io::Result<()> {
+        let dur = match self.next_timeout() {
+            Some(timeout) if !self.inner.clock.is_paused() => timeout.min(dur),
+            _ => dur,
+        };
+        self.park.park_timeout(dur)?;
+        self.process();
+        Ok(())
+    }
+    fn park_idle(&mut self) -> io::Result<()> {
+        match self.next_timeout() {
+            Some(timeout) if self.inner.clock.is_paused() => {
+                self.park_paused(timeout)?;
+                self.process();
+                Ok(())
+            }
+            _ => self.park(),
+        }
+    }
+}
+
+impl<U: Unpark> Unpark for Unparker<U> {
+    fn unpark(&self) {
+        self.woken.store(true, Ordering::SeqCst);
+        self.unpark.unpark();
+    }
+}
+
+impl Handle {
+    pub(super) fn inner(&self) -> Option<Arc<Inner>> {
+        self.inner.upgrade()
+    }
+
+    /// Current time according to the driver's clock.
+    pub fn now(&self) -> Instant {
+        match self.inner() {
+            Some(inner) => inner.clock.now(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Freezes time, pending timers only fire when the clock is advanced.
+    pub fn pause(&self) {
+        if let Some(inner) = self.inner() {
+            inner.clock.pause();
+        }
+    }
+
+    pub fn resume(&self) {
+        if let Some(inner) = self.inner() {
+            inner.clock.resume();
+        }
+    }
+
+    /// Moves the paused clock forward, timers that are now due fire on the next park.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the clock is not paused.
+    pub fn advance(&self, duration: Duration) -> io::Result<()> {
+        if let Some(inner) = self.inner() {
+            inner.clock.advance(duration)?;
+            inner.unpark.unpark();
+        }
+        Ok(())
+    }
+}
+
+impl Inner {
+    /// Converts an instant to a tick, rounding up deadlines so that timers never fire early.
+    fn tick_for(&self, t: Instant, round_up: bool) -> u64 {
+        let since = t.saturating_duration_since(self.start);
+        let ms = since.as_millis() as u64;
+        if round_up && since > Duration::from_millis(ms) {
+            ms + 1
+        } else {
+            ms
+        }
+    }
+
+    /// Registers a timer firing at `deadline`, returns its key.
+    pub(super) fn insert(&self, deadline: Instant, waker: &Waker) -> usize {
+        let mut state = self.state.lock().unwrap();
+
+        let elapsed = state.wheel.elapsed();
+        let when = self.tick_for(deadline, true).min(elapsed + MAX_DURATION);
+
+        let key = state.entries.insert(Entry {
+            when,
+            waker: Some(waker.clone()),
+            fired: false,
+        });
+        self.schedule(state, key, when);
+        key
+    }
+
+    /// Moves an existing timer to a new deadline.
+    pub(super) fn reset(&self, key: usize, deadline: Instant) {
+        let mut state = self.state.lock().unwrap();
+
+        let old = state.entries[key].when;
+        state.wheel.remove(key, old);
+
+        let elapsed = state.wheel.elapsed();
+        let when = self.tick_for(deadline, true).min(elapsed + MAX_DURATION);
+
+        let entry = &mut state.entries[key];
+        entry.when = when;
+        entry.fired = false;
+        self.schedule(state, key, when);
+    }
+
+    fn schedule(&self, mut state: std::sync::MutexGuard<'_, State>, key: usize, when: u64) {
+        if state.wheel.insert(key, when).is_err() {
+            state.entries[key].fired = true;
+            return;
+        }
+
+        // The driver might be parked for longer than this timer, wake it up so that it
+        // recomputes its timeout.
+        let earlier = state.next_wake.is_none_or(|next| when < next);
+        if earlier {
+            state.next_wake = Some(when);
+        }
+        drop(state);
+
+        if earlier {
+            self.unpark.unpark();
+        }
+    }
+
+    pub(super) fn poll_elapsed(&self, key: usize, waker: &Waker) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        let entry = &mut state.entries[key];
+        if entry.fired {
+            return Poll::Ready(());
+        }
+        match entry.waker {
+            Some(ref w) if w.will_wake(waker) => {}
+            _ => entry.waker = Some(waker.clone()),
+        }
+        Poll::Pending
+    }
+
+    pub(super) fn remove(&self, key: usize) {
+        let mut state = self.state.lock().unwrap();
+        let entry = state.entries.remove(key);
+        if !entry.fired {
+            state.wheel.remove(key, entry.when);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::FutureExt;
+
+    use crate::io;
+    use crate::park::Park;
+    use crate::time::{self, Driver};
+
+    #[test]
+    fn paused_clock_only_advances_when_idle() {
+        let mut driver = Driver::new(io::Driver::new());
+        let handle = driver.handle();
+        assert!(handle.advance(Duration::from_secs(1)).is_err());
+
+        handle.pause();
+        let start = handle.now();
+        let mut sleep = Box::pin(time::sleep(&handle, Duration::from_secs(3600)));
+        assert!(sleep.as_mut().now_or_never().is_none());
+
+        driver.park_timeout(Duration::from_millis(0)).unwrap();
+        assert_eq!(handle.now(), start);
+
+        // The wheel may stop at the start of a coarse slot before reaching the deadline.
+        let fired = (0..8).any(|_| {
+            driver.park_idle().unwrap();
+            sleep.as_mut().now_or_never().is_some()
+        });
+        assert!(fired);
+        let elapsed = handle.now() - start;
+        assert!(elapsed >= Duration::from_secs(3600) && elapsed < Duration::from_secs(3601));
+
+        handle.advance(Duration::from_secs(1)).unwrap();
+        assert_eq!(handle.now() - start, elapsed + Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use super::{sleep_until, Handle, Sleep};
+
+use futures::{future::poll_fn, ready, stream::Stream};
+
+/// Stream yielding every `period`, the first tick completes at `start`. The stream ends when the
+/// timer driver goes away.
+pub struct Interval {
+    delay: Sleep,
+    period: Duration,
+}
+
+pub fn interval(handle: &Handle, period: Duration) -> Interval {
+    interval_at(handle, handle.now(), period)
+}
+
+pub fn interval_at(handle: &Handle, start: Instant, period: Duration) -> Interval {
+    assert!(
+        period > Duration::from_millis(0),
+        "`period` must be non-zero"
+    );
+    Interval {
+        delay: sleep_until(handle, start),
+        period,
+    }
+}
+
+impl Interval {
+    /// Ticks right away once the timer driver is gone.
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        ready!(Pin::new(&mut self.delay).poll(cx));
+
+        let tick = self.delay.deadline();
+        self.delay.reset(tick + self.period);
+        Poll::Ready(tick)
+    }
+
+    pub async fn tick(&mut self) -> Instant {
+        poll_fn(|cx| self.poll_tick(cx)).await
+    }
+}
+
+impl Stream for Interval {
+    type Item = Instant;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Instant>> {
+        if self.delay.is_orphaned() {
+            return Poll::Ready(None);
+        }
+        self.get_mut().poll_tick(cx).map(Some)
+    }
+}
@@ -0,0 +1,12 @@
+mod clock;
+mod driver;
+mod interval;
+mod sleep;
+mod timeout;
+mod wheel;
+
+pub use clock::Clock;
+pub use driver::{Driver, Handle};
+pub use interval::{interval, interval_at, Interval};
+pub use sleep::{sleep, sleep_until, Sleep};
+pub use timeout::{timeout, timeout_at, Elapsed, Timeout};
@@ -0,0 +1,96 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use super::Handle;
+
+/// Future completing once `deadline` is reached, or right away if the timer driver is gone since
+/// nothing would ever fire it.
+pub struct Sleep {
+    handle: Handle,
+    deadline: Instant,
+    key: Option<usize>,
+}
+
+pub fn sleep(handle: &Handle, duration: Duration) -> Sleep {
+    sleep_until(handle, handle.now() + duration)
+}
+
+pub fn sleep_until(handle: &Handle, deadline: Instant) -> Sleep {
+    Sleep {
+        handle: handle.clone(),
+        deadline,
+        key: None,
+    }
+}
+
+impl Sleep {
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// True once the timer driver is gone, the sleep completes without waiting.
+    pub fn is_orphaned(&self) -> bool {
+        self.handle.inner().is_none()
+    }
+
+    /// Changes the deadline, the sleep can be awaited again after it completed.
+    pub fn reset(&mut self, deadline: Instant) {
+        self.deadline = deadline;
+        if let (Some(key), Some(inner)) = (self.key, self.handle.inner()) {
+            inner.reset(key, deadline);
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let inner = match self.handle.inner() {
+            Some(inner) => inner,
+            None => return Poll::Ready(()),
+        };
+        match self.key {
+            Some(key) => inner.poll_elapsed(key, cx.waker()),
+            None => {
+                let key = inner.insert(self.deadline, cx.waker());
+                self.key = Some(key);
+                inner.poll_elapsed(key, cx.waker())
+            }
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let (Some(key), Some(inner)) = (self.key, self.handle.inner()) {
+            inner.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{executor::block_on, StreamExt};
+
+    use crate::io;
+    use crate::time::{self, Driver};
+
+    #[test]
+    fn outliving_the_driver() {
+        let driver = Driver::new(io::Driver::new());
+        let handle = driver.handle();
+        let sleep = time::sleep(&handle, Duration::from_secs(3600));
+        let mut interval = time::interval(&handle, Duration::from_secs(3600));
+        drop(driver);
+
+        block_on(sleep);
+        assert_eq!(block_on(interval.next()), None);
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    fmt,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use super::{sleep_until, Handle, Sleep};
+
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Future resolving to the output of `future`, or `Elapsed` if the deadline is reached
+    /// first.
+    pub struct Timeout<F> {
+        #[pin]
+        future: F,
+        delay: Sleep,
+    }
+}
+
+/// Error returned by `Timeout` when the deadline was reached.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Elapsed(());
+
+pub fn timeout<F: Future>(handle: &Handle, duration: Duration, future: F) -> Timeout<F> {
+    timeout_at(handle, handle.now() + duration, future)
+}
+
+pub fn timeout_at<F: Future>(handle: &Handle, deadline: Instant, future: F) -> Timeout<F> {
+    Timeout {
+        future,
+        delay: sleep_until(handle, deadline),
+    }
+}
+
+impl<F> Timeout<F> {
+    pub fn into_inner(self) -> F {
+        self.future
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = self.project();
+
+        if let Poll::Ready(output) = me.future.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+
+        match Pin::new(me.delay).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed(()))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+impl From<Elapsed> for io::Error {
+    fn from(_: Elapsed) -> io::Error {
+        io::ErrorKind::TimedOut.into()
+    }
+}
@@ -0,0 +1,175 @@
+/// Number of levels in the wheel, each level covers 64 times the range of the previous one.
+const LEVELS: usize = 6;
+const SLOTS: usize = 64;
+const SLOT_BITS: u64 = 6;
+
+/// Furthest tick that can be stored in the wheel relative to `elapsed` (~2 years in ms).
+pub(super) const MAX_DURATION: u64 = (1 << (SLOT_BITS * LEVELS as u64)) - 1;
+
+/// Hierarchical hashed timer wheel keyed by millisecond ticks.
+///
+/// Level `n` has 64 slots each covering `64^n` ticks; timers are stored at the level where
+/// their deadline and the current tick differ in the most significant bits, and cascade down
+/// to lower levels as time advances.
+pub(super) struct Wheel {
+    elapsed: u64,
+    levels: Vec<Level>,
+}
+
+struct Level {
+    level: usize,
+    occupied: u64,
+    slots: Vec<Vec<(usize, u64)>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Expiration {
+    level: usize,
+    slot: usize,
+    pub(super) deadline: u64,
+}
+
+impl Wheel {
+    pub(super) fn new() -> Self {
+        Self {
+            elapsed: 0,
+            levels: (0..LEVELS).map(Level::new).collect(),
+        }
+    }
+
+    pub(super) fn elapsed(&self) -> u64 {
+        self.elapsed
+    }
+
+    /// Stores `key` to be fired at tick `when`, gives it back if `when` has already elapsed.
+    ///
+    /// `when` must not be more than `MAX_DURATION` ticks after `elapsed`.
+    pub(super) fn insert(&mut self, key: usize, when: u64) -> Result<(), usize> {
+        if when <= self.elapsed {
+            return Err(key);
+        }
+        debug_assert!(when - self.elapsed <= MAX_DURATION);
+        let level = level_for(self.elapsed, when);
+        self.levels[level].add(key, when);
+        Ok(())
+    }
+
+    pub(super) fn remove(&mut self, key: usize, when: u64) {
+        if when <= self.elapsed {
+            return;
+        }
+        let level = level_for(self.elapsed, when);
+        self.levels[level].remove(key, when);
+    }
+
+    /// Earliest slot that needs processing.
+    pub(super) fn next_expiration(&self) -> Option<Expiration> {
+        self.levels
+            .iter()
+            .find_map(|level| level.next_expiration(self.elapsed))
+    }
+
+    /// Advances the wheel to `now`, returns the keys of every expired timer.
+    pub(super) fn poll(&mut self, now: u64) -> Vec<usize> {
+        let mut expired = Vec::new();
+
+        while let Some(expiration) = self.next_expiration() {
+            if expiration.deadline > now {
+                break;
+            }
+
+            let entries = self.levels[expiration.level].take(expiration.slot);
+            self.elapsed = expiration.deadline;
+
+            for (key, when) in entries {
+                // Either expired or cascades down to a lower level.
+                if let Err(key) = self.insert(key, when) {
+                    expired.push(key);
+                }
+            }
+        }
+
+        if now > self.elapsed {
+            self.elapsed = now;
+        }
+        expired
+    }
+}
+
+impl Level {
+    fn new(level: usize) -> Self {
+        Self {
+            level,
+            occupied: 0,
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    fn add(&mut self, key: usize, when: u64) {
+        let slot = slot_for(when, self.level);
+        self.slots[slot].push((key, when));
+        self.occupied |= 1 << slot;
+    }
+
+    fn remove(&mut self, key: usize, when: u64) {
+        let slot = slot_for(when, self.level);
+        let entries = &mut self.slots[slot];
+        if let Some(position) = entries.iter().position(|&(k, _)| k == key) {
+            entries.swap_remove(position);
+        }
+        if entries.is_empty() {
+            self.occupied &= !(1 << slot);
+        }
+    }
+
+    fn take(&mut self, slot: usize) -> Vec<(usize, u64)> {
+        self.occupied &= !(1 << slot);
+        std::mem::take(&mut self.slots[slot])
+    }
+
+    fn next_expiration(&self, now: u64) -> Option<Expiration> {
+        if self.occupied == 0 {
+            return None;
+        }
+
+        let slot_range = slot_range(self.level);
+        let level_range = slot_range * SLOTS as u64;
+
+        let now_slot = (now / slot_range) as u32 % SLOTS as u32;
+        let zeros = self.occupied.rotate_right(now_slot).trailing_zeros();
+        let slot = (zeros + now_slot) as usize % SLOTS;
+
+        let level_start = now & !(level_range - 1);
+        let mut deadline = level_start + slot as u64 * slot_range;
+        if deadline <= now {
+            // Only the top level wraps around, its slots act as a ring buffer.
+            debug_assert_eq!(self.level, LEVELS - 1);
+            deadline += level_range;
+        }
+
+        Some(Expiration {
+            level: self.level,
+            slot,
+            deadline,
+        })
+    }
+}
+
+fn slot_range(level: usize) -> u64 {
+    1 << (SLOT_BITS * level as u64)
+}
+
+fn slot_for(when: u64, level: usize) -> usize {
+    ((when >> (SLOT_BITS * level as u64)) % SLOTS as u64) as usize
+}
+
+fn level_for(elapsed: u64, when: u64) -> usize {
+    const SLOT_MASK: u64 = (1 << SLOT_BITS) - 1;
+
+    let mut masked = (elapsed ^ when) | SLOT_MASK;
+    if masked >= MAX_DURATION {
+        masked = MAX_DURATION - 1;
+    }
+    let significant = 63 - masked.leading_zeros() as u64;
+    (significant / SLOT_BITS) as usize
+}
@@ -0,0 +1,167 @@
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    task::{Context, Poll},
+};
+
+use crate::io::{Handle, Interest, PollEvented, Ready};
+
+use futures::ready;
+
+/// UDP socket driven by the IO driver.
+///
+/// Every operation takes `&self`: a receiving task and a sending task can share the socket,
+/// they wait on the read and write readiness respectively.
+pub struct UdpSocket {
+    io: PollEvented<mio::net::UdpSocket>,
+}
+
+impl UdpSocket {
+    pub fn new(handle: Handle, socket: mio::net::UdpSocket) -> io::Result<UdpSocket> {
+        // Sending is done through `&self`, the writable interest can't be added lazily.
+        let io = PollEvented::new_with_interest(handle, socket, Interest::both())?;
+        Ok(UdpSocket { io })
+    }
+
+    pub fn bind(handle: Handle, addr: SocketAddr) -> io::Result<Self> {
+        let sys = mio::net::UdpSocket::bind(addr)?;
+        Self::new(handle, sys)
+    }
+
+    /// Sets the default destination of `send` and filters the datagrams read by `recv`.
+    pub fn connect(&self, addr: SocketAddr) -> io::Result<()> {
+        self.io.get_ref().connect(addr)
+    }
+
+    pub async fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.poll_send_to(cx, buf, target)).await
+    }
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        futures::future::poll_fn(|cx| self.poll_recv_from(cx, buf)).await
+    }
+    /// Receives a datagram without removing it from the queue.
+    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        futures::future::poll_fn(|cx| self.poll_peek_from(cx, buf)).await
+    }
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.poll_send(cx, buf)).await
+    }
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.poll_recv(cx, buf)).await
+    }
+
+    pub fn poll_send_to(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+        target: SocketAddr,
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_io(cx, |socket| socket.send_to(buf, target))
+    }
+    pub fn poll_recv_from(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<(usize, SocketAddr)>> {
+        self.poll_read_io(cx, |socket| socket.recv_from(buf))
+    }
+    pub fn poll_peek_from(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<(usize, SocketAddr)>> {
+        self.poll_read_io(cx, |socket| socket.peek_from(buf))
+    }
+    pub fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.poll_write_io(cx, |socket| socket.send(buf))
+    }
+    pub fn poll_recv(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.poll_read_io(cx, |socket| socket.recv(buf))
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().take_error()
+    }
+
+    pub fn set_broadcast(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_broadcast(on)
+    }
+    pub fn broadcast(&self) -> io::Result<bool> {
+        self.io.get_ref().broadcast()
+    }
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.io.get_ref().set_ttl(ttl)
+    }
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.io.get_ref().ttl()
+    }
+
+    pub fn join_multicast_v4(&self, multiaddr: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.io.get_ref().join_multicast_v4(&multiaddr, &interface)
+    }
+    pub fn leave_multicast_v4(&self, multiaddr: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.io.get_ref().leave_multicast_v4(&multiaddr, &interface)
+    }
+    pub fn join_multicast_v6(&self, multiaddr: &Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.io.get_ref().join_multicast_v6(multiaddr, interface)
+    }
+    pub fn leave_multicast_v6(&self, multiaddr: &Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.io.get_ref().leave_multicast_v6(multiaddr, interface)
+    }
+    pub fn set_multicast_loop_v4(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_multicast_loop_v4(on)
+    }
+    pub fn multicast_loop_v4(&self) -> io::Result<bool> {
+        self.io.get_ref().multicast_loop_v4()
+    }
+    pub fn set_multicast_ttl_v4(&self, ttl: u32) -> io::Result<()> {
+        self.io.get_ref().set_multicast_ttl_v4(ttl)
+    }
+    pub fn multicast_ttl_v4(&self) -> io::Result<u32> {
+        self.io.get_ref().multicast_ttl_v4()
+    }
+    pub fn set_multicast_loop_v6(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_multicast_loop_v6(on)
+    }
+    pub fn multicast_loop_v6(&self) -> io::Result<bool> {
+        self.io.get_ref().multicast_loop_v6()
+    }
+
+    fn poll_read_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        f: impl FnOnce(&mio::net::UdpSocket) -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        ready!(self.io.poll_read_ready(cx, Ready::readable()))?;
+
+        match f(self.io.get_ref()) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(cx, Ready::readable())?;
+                Poll::Pending
+            }
+            x => Poll::Ready(x),
+        }
+    }
+
+    fn poll_write_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        f: impl FnOnce(&mio::net::UdpSocket) -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        ready!(self.io.poll_write_ready(cx))?;
+
+        match f(self.io.get_ref()) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_write_ready(cx)?;
+                Poll::Pending
+            }
+            x => Poll::Ready(x),
+        }
+    }
+}
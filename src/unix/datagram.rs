@@ -0,0 +1,130 @@
+use std::{
+    io,
+    mem::ManuallyDrop,
+    os::unix::{
+        io::{AsRawFd, FromRawFd},
+        net,
+    },
+    path::Path,
+    task::{Context, Poll},
+};
+
+use super::SocketAddr;
+use crate::io::{Handle, Interest, PollEvented, Ready};
+
+use futures::ready;
+
+/// Unix datagram socket driven by the IO driver, shares the `&self` API of `udp::UdpSocket`.
+pub struct UnixDatagram {
+    io: PollEvented<mio::net::UnixDatagram>,
+}
+
+impl UnixDatagram {
+    pub fn new(handle: Handle, socket: mio::net::UnixDatagram) -> io::Result<UnixDatagram> {
+        // Sending is done through `&self`, the writable interest can't be added lazily.
+        let io = PollEvented::new_with_interest(handle, socket, Interest::both())?;
+        Ok(UnixDatagram { io })
+    }
+
+    pub fn bind(handle: Handle, path: impl AsRef<Path>) -> io::Result<Self> {
+        let sys = mio::net::UnixDatagram::bind(path)?;
+        Self::new(handle, sys)
+    }
+
+    /// Binds to `addr`, which may be an abstract address on Linux.
+    pub fn bind_addr(handle: Handle, addr: &net::SocketAddr) -> io::Result<Self> {
+        let std = net::UnixDatagram::bind_addr(addr)?;
+        std.set_nonblocking(true)?;
+        Self::new(handle, mio::net::UnixDatagram::from_std(std))
+    }
+
+    pub fn unbound(handle: Handle) -> io::Result<Self> {
+        Self::new(handle, mio::net::UnixDatagram::unbound()?)
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    pub fn pair(handle: Handle) -> io::Result<(Self, Self)> {
+        let (a, b) = mio::net::UnixDatagram::pair()?;
+        Ok((Self::new(handle.clone(), a)?, Self::new(handle, b)?))
+    }
+
+    pub fn connect(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.io.get_ref().connect(path)
+    }
+    pub fn connect_addr(&self, addr: &net::SocketAddr) -> io::Result<()> {
+        self.with_std(|socket| socket.connect_addr(addr))
+    }
+
+    pub async fn send_to(&self, buf: &[u8], path: impl AsRef<Path>) -> io::Result<usize> {
+        let path = path.as_ref();
+        futures::future::poll_fn(|cx| self.poll_write_io(cx, |socket| socket.send_to(buf, path)))
+            .await
+    }
+    pub async fn send_to_addr(&self, buf: &[u8], addr: &net::SocketAddr) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| {
+            self.poll_write_io(cx, |_| {
+                self.with_std(|socket| socket.send_to_addr(buf, addr))
+            })
+        })
+        .await
+    }
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        futures::future::poll_fn(|cx| self.poll_read_io(cx, |socket| socket.recv_from(buf))).await
+    }
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.poll_write_io(cx, |socket| socket.send(buf))).await
+    }
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.poll_read_io(cx, |socket| socket.recv(buf))).await
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().take_error()
+    }
+
+    /// mio only takes paths, the operations on `net::SocketAddr` go through a std socket
+    /// borrowing the file descriptor.
+    fn with_std<R>(&self, f: impl FnOnce(&net::UnixDatagram) -> io::Result<R>) -> io::Result<R> {
+        let fd = self.io.get_ref().as_raw_fd();
+        let socket = ManuallyDrop::new(unsafe { net::UnixDatagram::from_raw_fd(fd) });
+        f(&socket)
+    }
+
+    fn poll_read_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        f: impl FnOnce(&mio::net::UnixDatagram) -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        ready!(self.io.poll_read_ready(cx, Ready::readable()))?;
+
+        match f(self.io.get_ref()) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(cx, Ready::readable())?;
+                Poll::Pending
+            }
+            x => Poll::Ready(x),
+        }
+    }
+
+    fn poll_write_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        f: impl FnOnce(&mio::net::UnixDatagram) -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        ready!(self.io.poll_write_ready(cx))?;
+
+        match f(self.io.get_ref()) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_write_ready(cx)?;
+                Poll::Pending
+            }
+            x => Poll::Ready(x),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    io,
+    os::unix::net,
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use super::{SocketAddr, UnixStream};
+use crate::io::{Handle, PollEvented, Ready};
+
+use futures::{ready, stream::Stream};
+
+pub struct UnixListener {
+    io: PollEvented<mio::net::UnixListener>,
+    handle: Handle,
+}
+
+/// Stream of the connections accepted by a `UnixListener`.
+pub struct Incoming<'a> {
+    listener: &'a UnixListener,
+}
+
+impl UnixListener {
+    pub fn new(handle: Handle, listener: mio::net::UnixListener) -> io::Result<UnixListener> {
+        let io = PollEvented::new(handle.clone(), listener)?;
+        Ok(UnixListener { io, handle })
+    }
+
+    pub fn bind(handle: Handle, path: impl AsRef<Path>) -> io::Result<Self> {
+        let sys = mio::net::UnixListener::bind(path)?;
+        Self::new(handle, sys)
+    }
+
+    /// Binds to `addr`, which may be an abstract address on Linux.
+    pub fn bind_addr(handle: Handle, addr: &net::SocketAddr) -> io::Result<Self> {
+        let std = net::UnixListener::bind_addr(addr)?;
+        std.set_nonblocking(true)?;
+        Self::new(handle, mio::net::UnixListener::from_std(std))
+    }
+
+    pub async fn accept(&self) -> io::Result<(UnixStream, SocketAddr)> {
+        futures::future::poll_fn(|cx| self.poll_accept(cx)).await
+    }
+
+    pub fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(UnixStream, SocketAddr)>> {
+        ready!(self.io.poll_read_ready(cx, Ready::readable()))?;
+
+        match self.io.get_ref().accept() {
+            Ok((io, addr)) => {
+                Poll::Ready(UnixStream::new(self.handle.clone(), io).map(|s| (s, addr)))
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(cx, Ready::readable())?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming { listener: self }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<UnixStream>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let (stream, _) = ready!(self.listener.poll_accept(cx))?;
+        Poll::Ready(Some(Ok(stream)))
+    }
+}
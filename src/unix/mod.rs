@@ -0,0 +1,18 @@
+mod datagram;
+mod listener;
+mod stream;
+mod ucred;
+
+pub use datagram::UnixDatagram;
+pub use listener::{Incoming, UnixListener};
+pub use mio::net::SocketAddr;
+pub use stream::UnixStream;
+pub use ucred::UCred;
+
+/// Address in the Linux abstract namespace, it has no presence in the filesystem and goes
+/// away with the last socket bound to it. Use it with the `*_addr` constructors.
+#[cfg(target_os = "linux")]
+pub fn abstract_addr(name: &[u8]) -> std::io::Result<std::os::unix::net::SocketAddr> {
+    use std::os::linux::net::SocketAddrExt;
+    std::os::unix::net::SocketAddr::from_abstract_name(name)
+}
@@ -1,11 +1,13 @@
 use std::{
     io::{self, Read, Write},
-    net::SocketAddr,
+    os::unix::{io::AsRawFd, net},
+    path::Path,
     pin::Pin,
     task::{Context, Poll},
 };
 
-use crate::io::{Handle, PollEvented};
+use super::{ucred, SocketAddr, UCred};
+use crate::io::{Handle, Interest, PollEvented, Ready};
 
 use futures::{
     io::{AsyncRead, AsyncWrite},
@@ -14,20 +16,37 @@ use futures::{
 
 use tokio::io::{AsyncRead as TokioAsyncRead, AsyncWrite as TokioAsyncWrite};
 
-pub struct TcpStream {
-    io: PollEvented<mio::net::TcpStream>,
+pub struct UnixStream {
+    io: PollEvented<mio::net::UnixStream>,
 }
 
-impl TcpStream {
-    pub fn new(handle: Handle, connected: mio::net::TcpStream) -> io::Result<TcpStream> {
-        let port = connected.peer_addr()?.port() as usize;
-        let io = PollEvented::new(handle, mio::Token(port), connected)?;
-        Ok(TcpStream { io })
+impl UnixStream {
+    pub fn new(handle: Handle, connected: mio::net::UnixStream) -> io::Result<UnixStream> {
+        let io = PollEvented::new(handle, connected)?;
+        Ok(UnixStream { io })
     }
 
-    pub async fn connect(handle: Handle, addr: SocketAddr) -> io::Result<Self> {
-        let sys = mio::net::TcpStream::connect(&addr)?;
-        let stream = Self::new(handle, sys)?;
+    pub async fn connect(handle: Handle, path: impl AsRef<Path>) -> io::Result<Self> {
+        let sys = mio::net::UnixStream::connect(path)?;
+        Self::connected(handle, sys).await
+    }
+
+    /// Connects to `addr`, which may be an abstract address on Linux.
+    pub async fn connect_addr(handle: Handle, addr: &net::SocketAddr) -> io::Result<Self> {
+        let std = net::UnixStream::connect_addr(addr)?;
+        std.set_nonblocking(true)?;
+        Self::connected(handle, mio::net::UnixStream::from_std(std)).await
+    }
+
+    /// Creates an unnamed pair of connected streams.
+    pub fn pair(handle: Handle) -> io::Result<(Self, Self)> {
+        let (a, b) = mio::net::UnixStream::pair()?;
+        Ok((Self::new(handle.clone(), a)?, Self::new(handle, b)?))
+    }
+
+    async fn connected(handle: Handle, sys: mio::net::UnixStream) -> io::Result<Self> {
+        let io = PollEvented::new_with_interest(handle, sys, Interest::both())?;
+        let stream = UnixStream { io };
 
         futures::future::poll_fn(|cx| stream.io.poll_write_ready(cx)).await?;
 
@@ -37,19 +56,29 @@ impl TcpStream {
 
         Ok(stream)
     }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        ucred::peer_cred(self.io.get_ref().as_raw_fd())
+    }
 }
 
-impl AsyncRead for TcpStream {
+impl AsyncRead for UnixStream {
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
-        ready!(self.io.poll_read_ready(cx, mio::Ready::readable()))?;
+        ready!(self.io.poll_read_ready(cx, Ready::readable()))?;
 
         match self.io.get_ref().read(buf) {
             Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                self.io.clear_read_ready(cx, mio::Ready::readable())?;
+                self.io.clear_read_ready(cx, Ready::readable())?;
                 Poll::Pending
             }
             x => Poll::Ready(x),
@@ -57,12 +86,13 @@ impl AsyncRead for TcpStream {
     }
 }
 
-impl AsyncWrite for TcpStream {
+impl AsyncWrite for UnixStream {
     fn poll_write(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
+        self.io.add_interest(Interest::writable())?;
         ready!(self.io.poll_write_ready(cx))?;
 
         match self.io.get_ref().write(buf) {
@@ -83,7 +113,7 @@ impl AsyncWrite for TcpStream {
     }
 }
 
-impl TokioAsyncRead for TcpStream {
+impl TokioAsyncRead for UnixStream {
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context,
@@ -93,7 +123,7 @@ impl TokioAsyncRead for TcpStream {
     }
 }
 
-impl TokioAsyncWrite for TcpStream {
+impl TokioAsyncWrite for UnixStream {
     fn poll_write(
         self: Pin<&mut Self>,
         cx: &mut Context,
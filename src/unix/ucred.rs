@@ -0,0 +1,60 @@
+use std::{io, mem, os::unix::io::RawFd};
+
+/// Credentials of the process on the other end of a Unix socket, as of when the connection
+/// was established.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UCred {
+    pid: Option<libc::pid_t>,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+}
+
+impl UCred {
+    /// Not available on every platform.
+    pub fn pid(&self) -> Option<libc::pid_t> {
+        self.pid
+    }
+    pub fn uid(&self) -> libc::uid_t {
+        self.uid
+    }
+    pub fn gid(&self) -> libc::gid_t {
+        self.gid
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(super) fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let mut cred: libc::ucred = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if res != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(UCred {
+        pid: Some(cred.pid),
+        uid: cred.uid,
+        gid: cred.gid,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(super) fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let mut uid = mem::MaybeUninit::uninit();
+    let mut gid = mem::MaybeUninit::uninit();
+    if unsafe { libc::getpeereid(fd, uid.as_mut_ptr(), gid.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(UCred {
+        pid: None,
+        uid: unsafe { uid.assume_init() },
+        gid: unsafe { gid.assume_init() },
+    })
+}
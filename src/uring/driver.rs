@@ -0,0 +1,381 @@
+use std::{
+    any::Any,
+    cell::UnsafeCell,
+    io, mem,
+    os::unix::io::RawFd,
+    sync::{Arc, Mutex, Weak},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use io_uring::{opcode, squeue, types, IoUring, Probe};
+use slab::Slab;
+
+use crate::io as readiness;
+use crate::park::{Park, Unpark};
+
+const ENTRIES: u32 = 256;
+/// User data of the read pending on the eventfd, completes when the driver is unparked.
+const WAKE_TOKEN: u64 = u64::MAX;
+/// User data of the cancellations issued when the driver is dropped.
+const CANCEL_TOKEN: u64 = u64::MAX - 1;
+
+/// `io_uring_enter` flags, `io-uring` only sets them from its submitting calls.
+const IORING_ENTER_GETEVENTS: u32 = 1;
+const IORING_ENTER_EXT_ARG: u32 = 1 << 3;
+
+/// Kernel's `io_uring_getevents_arg`, bounds a wait on completions without submitting.
+#[repr(C)]
+struct GeteventsArg {
+    sigmask: u64,
+    sigmask_sz: u32,
+    pad: u32,
+    ts: u64,
+}
+
+/// Kernel's `__kernel_timespec`.
+#[repr(C)]
+struct KernelTimespec {
+    tv_sec: i64,
+    tv_nsec: i64,
+}
+
+/// IO driver submitting operations to io_uring and waking their futures on completion.
+///
+/// Falls back to the readiness `io::Driver` when the kernel does not support io_uring or
+/// lacks one of the operations used by this module, the resources created from its `Handle`
+/// follow the same backend.
+pub enum Driver {
+    Uring(Uring),
+    Readiness(readiness::Driver),
+}
+
+#[derive(Clone)]
+pub enum Handle {
+    Uring(Weak<Inner>),
+    Readiness(readiness::Handle),
+}
+
+pub struct Uring {
+    inner: Arc<Inner>,
+}
+
+pub struct Inner {
+    ring: IoUring,
+    /// Serializes pushes onto the submission queue and their submission, completions are only
+    /// reaped by the thread owning the driver.
+    submit: Mutex<()>,
+    ops: Mutex<Slab<Lifecycle>>,
+    eventfd: RawFd,
+    /// Target of the read pending on the eventfd, only written by the kernel.
+    wake_buf: Box<UnsafeCell<u64>>,
+}
+
+enum Lifecycle {
+    Submitted,
+    Waiting(Waker),
+    Completed(i32),
+    /// The operation was dropped before completing, whatever it lent to the kernel is kept
+    /// alive until the completion shows up.
+    Ignored {
+        _keep: Box<dyn Any + Send>,
+    },
+}
+
+impl Driver {
+    pub fn new() -> Self {
+        match Uring::new() {
+            Ok(uring) => Self::Uring(uring),
+            Err(_) => Self::Readiness(readiness::Driver::new()),
+        }
+    }
+
+    pub fn is_uring(&self) -> bool {
+        matches!(self, Self::Uring(_))
+    }
+
+    pub fn handle(&self) -> Handle {
+        match self {
+            Self::Uring(uring) => Handle::Uring(Arc::downgrade(&uring.inner)),
+            Self::Readiness(driver) => Handle::Readiness(driver.handle()),
+        }
+    }
+}
+
+impl Default for Driver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Uring {
+    fn new() -> io::Result<Self> {
+        let ring = IoUring::new(ENTRIES)?;
+
+        let mut probe = Probe::new();
+        ring.submitter().register_probe(&mut probe)?;
+        let required = [
+            opcode::Read::CODE,
+            opcode::Recv::CODE,
+            opcode::Send::CODE,
+            opcode::Accept::CODE,
+            opcode::Connect::CODE,
+            opcode::AsyncCancel::CODE,
+        ];
+        if !required.iter().all(|&code| probe.is_supported(code)) {
+            return Err(io::Error::other("io_uring lacks required operations"));
+        }
+        // Timed waits pass their timeout to `io_uring_enter` directly.
+        if !ring.params().is_feature_ext_arg() {
+            return Err(io::Error::other("io_uring lacks timed waits"));
+        }
+
+        let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        if eventfd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let uring = Self {
+            inner: Arc::new(Inner {
+                ring,
+                submit: Mutex::new(()),
+                ops: Mutex::new(Slab::new()),
+                eventfd,
+                wake_buf: Box::new(UnsafeCell::new(0)),
+            }),
+        };
+        uring.arm_wake()?;
+        Ok(uring)
+    }
+
+    fn arm_wake(&self) -> io::Result<()> {
+        let entry = opcode::Read::new(
+            types::Fd(self.inner.eventfd),
+            self.inner.wake_buf.get() as *mut u8,
+            mem::size_of::<u64>() as u32,
+        )
+        .build()
+        .user_data(WAKE_TOKEN);
+        self.inner.push(&entry)
+    }
+
+    fn turn(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        let res = match timeout {
+            Some(timeout) if timeout == Duration::from_millis(0) => self.inner.flush(),
+            timeout => self.inner.flush().and_then(|_| self.inner.wait(timeout)),
+        };
+        match res {
+            Ok(_) => {}
+            // Interrupted, timed out, or the completion queue is full: reap what is there.
+            Err(ref e) if e.raw_os_error() == Some(libc::EINTR) => {}
+            Err(ref e) if e.raw_os_error() == Some(libc::ETIME) => {}
+            Err(ref e) if e.raw_os_error() == Some(libc::EBUSY) => {}
+            Err(e) => return Err(e),
+        }
+
+        let mut woken = false;
+        let mut wakers = Vec::new();
+        {
+            // Only the driver consumes the completion queue.
+            let completions = unsafe { self.inner.ring.completion_shared() };
+            let mut ops = self.inner.ops.lock().unwrap();
+            for cqe in completions {
+                match cqe.user_data() {
+                    WAKE_TOKEN => woken = true,
+                    CANCEL_TOKEN => {}
+                    key => {
+                        if let Some(waker) = complete(&mut ops, key as usize, cqe.result()) {
+                            wakers.push(waker);
+                        }
+                    }
+                }
+            }
+        }
+        for waker in wakers {
+            waker.wake();
+        }
+
+        if woken {
+            self.arm_wake()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Uring {
+    fn drop(&mut self) {
+        // The kernel may still write into buffers owned by pending operations, cancel them
+        // and wait for their completions before the ring and the buffers go away.
+        let keys: Vec<usize> = {
+            let ops = self.inner.ops.lock().unwrap();
+            ops.iter()
+                .filter(|(_, op)| !matches!(op, Lifecycle::Completed(_)))
+                .map(|(key, _)| key)
+                .collect()
+        };
+        for key in keys {
+            let entry = opcode::AsyncCancel::new(key as u64)
+                .build()
+                .user_data(CANCEL_TOKEN);
+            let _ = self.inner.push(&entry);
+        }
+        while self.inner.in_flight() {
+            if self.turn(None).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.eventfd) };
+    }
+}
+
+// The wake-up buffer is only ever written by the kernel.
+unsafe impl Send for Inner {}
+unsafe impl Sync for Inner {}
+
+/// Records the result of an operation, returns the waker of the task waiting on it.
+fn complete(ops: &mut Slab<Lifecycle>, key: usize, res: i32) -> Option<Waker> {
+    let slot = ops.get_mut(key)?;
+    match mem::replace(slot, Lifecycle::Completed(res)) {
+        Lifecycle::Waiting(waker) => Some(waker),
+        Lifecycle::Ignored { .. } => {
+            ops.remove(key);
+            None
+        }
+        Lifecycle::Submitted | Lifecycle::Completed(_) => None,
+    }
+}
+
+impl Inner {
+    /// Pushes an entry on the submission queue and submits it, flushing the queue to the
+    /// kernel first if full. Only fails if the entry could not be queued.
+    fn push(&self, entry: &squeue::Entry) -> io::Result<()> {
+        let _guard = self.submit.lock().unwrap();
+        loop {
+            let pushed = unsafe { self.ring.submission_shared().push(entry).is_ok() };
+            if pushed {
+                break;
+            }
+            self.ring.submit()?;
+        }
+        // The entry is queued whatever happens now, a failed submit is retried by the next
+        // turn of the driver.
+        let _ = self.ring.submit();
+        Ok(())
+    }
+
+    /// Submits whatever is left on the submission queue.
+    fn flush(&self) -> io::Result<usize> {
+        let _guard = self.submit.lock().unwrap();
+        self.ring.submit()
+    }
+
+    /// Waits for a completion without submitting, so that the submission lock is not held
+    /// while blocked.
+    fn wait(&self, timeout: Option<Duration>) -> io::Result<usize> {
+        let submitter = self.ring.submitter();
+        match timeout {
+            Some(timeout) => {
+                let ts = KernelTimespec {
+                    tv_sec: timeout.as_secs() as i64,
+                    tv_nsec: timeout.subsec_nanos() as i64,
+                };
+                let arg = GeteventsArg {
+                    sigmask: 0,
+                    sigmask_sz: 0,
+                    pad: 0,
+                    ts: &ts as *const KernelTimespec as u64,
+                };
+                let flags = IORING_ENTER_GETEVENTS | IORING_ENTER_EXT_ARG;
+                unsafe { submitter.enter(0, 1, flags, Some(&arg)) }
+            }
+            None => unsafe {
+                submitter.enter::<libc::sigset_t>(0, 1, IORING_ENTER_GETEVENTS, None)
+            },
+        }
+    }
+
+    /// Submits an operation, the entry's user data is overwritten with the operation key.
+    pub(super) fn submit_op(&self, entry: squeue::Entry) -> io::Result<usize> {
+        let key = self.ops.lock().unwrap().insert(Lifecycle::Submitted);
+        let entry = entry.user_data(key as u64);
+
+        if let Err(e) = self.push(&entry) {
+            self.ops.lock().unwrap().remove(key);
+            return Err(e);
+        }
+        Ok(key)
+    }
+
+    pub(super) fn poll_op(&self, key: usize, cx: &mut Context<'_>) -> Poll<i32> {
+        let mut ops = self.ops.lock().unwrap();
+        let slot = &mut ops[key];
+        match slot {
+            Lifecycle::Completed(res) => {
+                let res = *res;
+                ops.remove(key);
+                Poll::Ready(res)
+            }
+            Lifecycle::Waiting(waker) if waker.will_wake(cx.waker()) => Poll::Pending,
+            _ => {
+                *slot = Lifecycle::Waiting(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    fn in_flight(&self) -> bool {
+        let ops = self.ops.lock().unwrap();
+        ops.iter()
+            .any(|(_, op)| !matches!(op, Lifecycle::Completed(_)))
+    }
+
+    /// Called when an operation is dropped, `data` is released once the kernel is done.
+    pub(super) fn drop_op(&self, key: usize, data: Box<dyn Any + Send>) {
+        let mut ops = self.ops.lock().unwrap();
+        let slot = &mut ops[key];
+        if let Lifecycle::Completed(_) = slot {
+            ops.remove(key);
+        } else {
+            *slot = Lifecycle::Ignored { _keep: data };
+        }
+    }
+}
+
+impl Park for Driver {
+    type Handle = Handle;
+
+    fn handle(&self) -> Self::Handle {
+        self.handle()
+    }
+    fn park(&mut self) -> io::Result<()> {
+        match self {
+            Self::Uring(uring) => uring.turn(None),
+            Self::Readiness(driver) => driver.park(),
+        }
+    }
+    fn park_timeout(&mut self, dur: Duration) -> io::Result<()> {
+        match self {
+            Self::Uring(uring) => uring.turn(Some(dur)),
+            Self::Readiness(driver) => driver.park_timeout(dur),
+        }
+    }
+}
+
+impl Unpark for Handle {
+    fn unpark(&self) {
+        match self {
+            Self::Uring(inner) => {
+                if let Some(inner) = inner.upgrade() {
+                    let one = 1u64.to_ne_bytes();
+                    unsafe { libc::write(inner.eventfd, one.as_ptr() as *const _, one.len()) };
+                }
+            }
+            Self::Readiness(handle) => handle.unpark(),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! Experimental completion based IO on top of io_uring, enabled with the `uring` feature.
+//!
+//! Operations own their buffers for as long as the kernel may access them, so reads and
+//! writes take a `Vec<u8>` and hand it back along with the result. When the kernel does not
+//! support io_uring everything transparently runs on the readiness `io::Driver` instead.
+
+mod addr;
+mod driver;
+mod op;
+mod tcp;
+
+pub use driver::{Driver, Handle};
+pub use tcp::{TcpListener, TcpStream};
@@ -0,0 +1,76 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    sync::Weak,
+    task::{Context, Poll},
+};
+
+use io_uring::squeue;
+
+use super::driver::Inner;
+
+/// In-flight operation, resolves to the kernel's result and gives back the resources that
+/// were lent to it.
+pub(super) struct Op<T: Send + 'static> {
+    inner: Weak<Inner>,
+    key: usize,
+    data: Option<T>,
+}
+
+impl<T: Send + 'static> Op<T> {
+    /// Submits the entry built from `data`, the entry may only point to heap memory owned by
+    /// `data` since `data` itself moves with the future. `data` is given back if the entry
+    /// could not be submitted.
+    pub(super) fn submit(
+        inner: &Weak<Inner>,
+        mut data: T,
+        build: impl FnOnce(&mut T) -> squeue::Entry,
+    ) -> Result<Self, (io::Error, T)> {
+        let driver = match inner.upgrade() {
+            Some(driver) => driver,
+            None => return Err((driver_gone(), data)),
+        };
+        let key = match driver.submit_op(build(&mut data)) {
+            Ok(key) => key,
+            Err(e) => return Err((e, data)),
+        };
+        Ok(Self {
+            inner: inner.clone(),
+            key,
+            data: Some(data),
+        })
+    }
+}
+
+impl<T: Send + Unpin + 'static> Future for Op<T> {
+    type Output = (io::Result<u32>, T);
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = &mut *self;
+        let res = match me.inner.upgrade() {
+            Some(inner) => match inner.poll_op(me.key, cx) {
+                Poll::Ready(res) if res < 0 => Err(io::Error::from_raw_os_error(-res)),
+                Poll::Ready(res) => Ok(res as u32),
+                Poll::Pending => return Poll::Pending,
+            },
+            None => Err(driver_gone()),
+        };
+        let data = me.data.take().expect("operation polled after completion");
+        Poll::Ready((res, data))
+    }
+}
+
+impl<T: Send + 'static> Drop for Op<T> {
+    fn drop(&mut self) {
+        if let Some(data) = self.data.take() {
+            if let Some(inner) = self.inner.upgrade() {
+                inner.drop_op(self.key, Box::new(data));
+            }
+        }
+    }
+}
+
+fn driver_gone() -> io::Error {
+    io::Error::other("driver gone")
+}
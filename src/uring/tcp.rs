@@ -0,0 +1,220 @@
+use std::{
+    io,
+    net::{self, SocketAddr},
+    os::unix::io::{AsRawFd, FromRawFd},
+    sync::Weak,
+};
+
+use io_uring::{opcode, types};
+
+use super::addr::RawAddr;
+use super::driver::{Handle, Inner};
+use super::op::Op;
+use crate::tcp;
+
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+/// TCP stream whose reads and writes are submitted to io_uring.
+///
+/// The stream borrows nothing from the caller: buffers are moved into the operation and given
+/// back with its result, which lets the kernel write into them after the call returned.
+pub struct TcpStream {
+    inner: Stream,
+}
+
+enum Stream {
+    Uring {
+        socket: net::TcpStream,
+        driver: Weak<Inner>,
+    },
+    Readiness(tcp::TcpStream),
+}
+
+pub struct TcpListener {
+    inner: Listener,
+}
+
+enum Listener {
+    Uring {
+        socket: net::TcpListener,
+        driver: Weak<Inner>,
+    },
+    Readiness(tcp::TcpListener),
+}
+
+impl TcpStream {
+    pub async fn connect(handle: &Handle, addr: SocketAddr) -> io::Result<Self> {
+        let driver = match handle {
+            Handle::Uring(driver) => driver,
+            Handle::Readiness(handle) => {
+                let stream = tcp::TcpStream::connect(handle.clone(), addr).await?;
+                return Ok(Self {
+                    inner: Stream::Readiness(stream),
+                });
+            }
+        };
+
+        let domain = match addr {
+            SocketAddr::V4(_) => libc::AF_INET,
+            SocketAddr::V6(_) => libc::AF_INET6,
+        };
+        let fd = unsafe { libc::socket(domain, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let socket = unsafe { net::TcpStream::from_raw_fd(fd) };
+
+        let addr = Box::new(RawAddr::from(&addr));
+        let op = Op::submit(driver, addr, |addr| {
+            opcode::Connect::new(types::Fd(fd), addr.as_ptr(), addr.len).build()
+        })
+        .map_err(|(e, _)| e)?;
+        op.await.0?;
+
+        Ok(Self {
+            inner: Stream::Uring {
+                socket,
+                driver: driver.clone(),
+            },
+        })
+    }
+
+    /// Reads into `buf` up to its capacity, its length is set to the number of bytes read.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `buf` has no capacity, a read of zero bytes
+    /// would otherwise look like the end of the stream.
+    pub async fn read(&mut self, mut buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        if buf.capacity() == 0 {
+            let e = io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "read into a zero capacity buffer",
+            );
+            return (Err(e), buf);
+        }
+        match &mut self.inner {
+            Stream::Uring { socket, driver } => {
+                let fd = socket.as_raw_fd();
+                let op = Op::submit(driver, buf, |buf| {
+                    opcode::Recv::new(types::Fd(fd), buf.as_mut_ptr(), buf.capacity() as u32)
+                        .build()
+                });
+                let (res, mut buf) = match op {
+                    Ok(op) => op.await,
+                    Err((e, buf)) => return (Err(e), buf),
+                };
+                if let Ok(n) = res {
+                    // The kernel initialized the first `n` bytes.
+                    unsafe { buf.set_len(n as usize) };
+                }
+                (res.map(|n| n as usize), buf)
+            }
+            Stream::Readiness(stream) => {
+                let capacity = buf.capacity();
+                buf.resize(capacity, 0);
+                let res = stream.read(&mut buf).await;
+                buf.truncate(*res.as_ref().unwrap_or(&0));
+                (res, buf)
+            }
+        }
+    }
+
+    /// Writes the content of `buf`, returns how many bytes were written.
+    pub async fn write(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        self.write_from(buf, 0).await
+    }
+
+    /// Writes the whole content of `buf`.
+    pub async fn write_all(&mut self, mut buf: Vec<u8>) -> (io::Result<()>, Vec<u8>) {
+        let mut written = 0;
+        while written < buf.len() {
+            let (res, b) = self.write_from(buf, written).await;
+            buf = b;
+            match res {
+                Ok(0) => {
+                    let e =
+                        io::Error::new(io::ErrorKind::WriteZero, "failed to write whole buffer");
+                    return (Err(e), buf);
+                }
+                Ok(n) => written += n,
+                Err(e) => return (Err(e), buf),
+            }
+        }
+        (Ok(()), buf)
+    }
+
+    async fn write_from(&mut self, buf: Vec<u8>, offset: usize) -> (io::Result<usize>, Vec<u8>) {
+        match &mut self.inner {
+            Stream::Uring { socket, driver } => {
+                let fd = socket.as_raw_fd();
+                let op = Op::submit(driver, buf, |buf| {
+                    let rest = &buf[offset..];
+                    opcode::Send::new(types::Fd(fd), rest.as_ptr(), rest.len() as u32).build()
+                });
+                match op {
+                    Ok(op) => {
+                        let (res, buf) = op.await;
+                        (res.map(|n| n as usize), buf)
+                    }
+                    Err((e, buf)) => (Err(e), buf),
+                }
+            }
+            Stream::Readiness(stream) => {
+                let res = stream.write(&buf[offset..]).await;
+                (res, buf)
+            }
+        }
+    }
+}
+
+impl TcpListener {
+    pub fn bind(handle: &Handle, addr: SocketAddr) -> io::Result<Self> {
+        let inner = match handle {
+            Handle::Uring(driver) => Listener::Uring {
+                socket: net::TcpListener::bind(addr)?,
+                driver: driver.clone(),
+            },
+            Handle::Readiness(handle) => {
+                Listener::Readiness(tcp::TcpListener::bind(handle.clone(), addr)?)
+            }
+        };
+        Ok(Self { inner })
+    }
+
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (socket, driver) = match &self.inner {
+            Listener::Uring { socket, driver } => (socket, driver),
+            Listener::Readiness(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                let stream = TcpStream {
+                    inner: Stream::Readiness(stream),
+                };
+                return Ok((stream, addr));
+            }
+        };
+
+        let fd = socket.as_raw_fd();
+        let op = Op::submit(driver, Box::new(RawAddr::empty()), |addr| {
+            opcode::Accept::new(types::Fd(fd), addr.as_mut_ptr(), &mut addr.len)
+                .flags(libc::SOCK_CLOEXEC)
+                .build()
+        })
+        .map_err(|(e, _)| e)?;
+        let (res, addr) = op.await;
+        let socket = unsafe { net::TcpStream::from_raw_fd(res? as i32) };
+
+        let stream = TcpStream {
+            inner: Stream::Uring {
+                socket,
+                driver: driver.clone(),
+            },
+        };
+        Ok((stream, addr.to_socket_addr()?))
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match &self.inner {
+            Listener::Uring { socket, .. } => socket.local_addr(),
+            Listener::Readiness(listener) => listener.local_addr(),
+        }
+    }
+}
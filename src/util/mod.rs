@@ -0,0 +1,5 @@
+//! Pieces shared by the schedulers, the blocking pool and the runtime.
+
+mod thread;
+
+pub use thread::ThreadConfig;
@@ -0,0 +1,81 @@
+use std::io;
+use std::sync::Arc;
+use std::thread;
+
+type Callback = Arc<dyn Fn() + Send + Sync>;
+
+/// Settings applied to the threads started by the schedulers and the blocking pool.
+#[derive(Clone)]
+pub struct ThreadConfig {
+    name: String,
+    stack_size: Option<usize>,
+    on_start: Option<Callback>,
+    on_stop: Option<Callback>,
+}
+
+impl ThreadConfig {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            stack_size: None,
+            on_start: None,
+            on_stop: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Same settings, threads are named `name` instead.
+    pub fn with_name(&self, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..self.clone()
+        }
+    }
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+    pub fn on_start(mut self, f: Callback) -> Self {
+        self.on_start = Some(f);
+        self
+    }
+    pub fn on_stop(mut self, f: Callback) -> Self {
+        self.on_stop = Some(f);
+        self
+    }
+
+    /// Starts a thread called `name` running `f` between the start and stop hooks.
+    pub(crate) fn spawn<F>(&self, name: String, f: F) -> io::Result<thread::JoinHandle<()>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let config = self.clone();
+        self.builder(name).spawn(move || {
+            config.started();
+            f();
+            config.stopped();
+        })
+    }
+
+    /// Thread builder with the name and stack size set, the hooks are left to the caller.
+    pub(crate) fn builder(&self, name: String) -> thread::Builder {
+        let builder = thread::Builder::new().name(name);
+        match self.stack_size {
+            Some(size) => builder.stack_size(size),
+            None => builder,
+        }
+    }
+
+    pub(crate) fn started(&self) {
+        if let Some(on_start) = &self.on_start {
+            on_start();
+        }
+    }
+    pub(crate) fn stopped(&self) {
+        if let Some(on_stop) = &self.on_stop {
+            on_stop();
+        }
+    }
+}